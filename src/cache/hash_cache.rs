@@ -0,0 +1,358 @@
+//! Persistent hash cache
+//!
+//! Stores exact, partial and perceptual hashes on disk so that unchanged files
+//! are not rehashed on the next scan. Entries are keyed by canonical path and
+//! are only reused while the file's size and modification time still match.
+//! Entries for files that no longer exist are dropped when the cache is saved,
+//! as are entries whose path is not valid UTF-8 and cannot be a JSON key.
+
+use crate::error::{DejaVuError, Result};
+use crate::hashing::{ExactHashAlgorithm, HashAlgorithm};
+use crate::models::FileInfo;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Bump when the on-disk layout or any hash definition changes
+const CACHE_VERSION: u32 = 2;
+
+const CACHE_FILE_NAME: &str = "hashes.json";
+
+/// Cached hashes for one file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CacheEntry {
+    size: u64,
+    modified_secs: u64,
+    modified_nanos: u32,
+    /// Hex partial hashes keyed by content hash algorithm name
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    partial_hashes: HashMap<String, String>,
+    /// Hex full content hashes keyed by content hash algorithm name
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    exact_hashes: HashMap<String, String>,
+    /// Perceptual hashes keyed by algorithm name
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    perceptual_hashes: HashMap<String, u64>,
+}
+
+impl CacheEntry {
+    fn for_file(file: &FileInfo) -> Self {
+        let (modified_secs, modified_nanos) = timestamp(file.modified);
+        Self {
+            size: file.size,
+            modified_secs,
+            modified_nanos,
+            ..Default::default()
+        }
+    }
+
+    fn matches(&self, file: &FileInfo) -> bool {
+        self.size == file.size && (self.modified_secs, self.modified_nanos) == timestamp(file.modified)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    entries: HashMap<PathBuf, CacheEntry>,
+}
+
+/// Cache hit/miss counters for one run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl CacheStats {
+    pub fn lookups(&self) -> usize {
+        self.hits + self.misses
+    }
+
+    pub fn hit_rate(&self) -> f64 {
+        if self.lookups() == 0 {
+            0.0
+        } else {
+            self.hits as f64 / self.lookups() as f64 * 100.0
+        }
+    }
+}
+
+/// On-disk hash cache shared between hashing threads
+pub struct HashCache {
+    path: PathBuf,
+    entries: Mutex<HashMap<PathBuf, CacheEntry>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl HashCache {
+    /// Default cache location: `$XDG_CACHE_HOME/dejavu/hashes.json`
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("dejavu").join(CACHE_FILE_NAME))
+    }
+
+    /// Load the cache from `path`
+    ///
+    /// A missing file, an unreadable file or a file written by another cache
+    /// version all result in an empty cache; the file is rewritten on `save`.
+    pub fn load(path: &Path) -> Self {
+        let entries = std::fs::read(path)
+            .ok()
+            .and_then(|data| serde_json::from_slice::<CacheFile>(&data).ok())
+            .filter(|file| file.version == CACHE_VERSION)
+            .map(|file| file.entries)
+            .unwrap_or_default();
+
+        Self::with_entries(path, entries)
+    }
+
+    /// Create an empty cache that will overwrite `path` on `save`
+    pub fn empty(path: &Path) -> Self {
+        Self::with_entries(path, HashMap::new())
+    }
+
+    fn with_entries(path: &Path, entries: HashMap<PathBuf, CacheEntry>) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            entries: Mutex::new(entries),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    /// Write the cache back to disk atomically, without entries for files
+    /// that are gone
+    pub fn save(&self) -> Result<()> {
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        std::fs::create_dir_all(dir)?;
+
+        let entries: HashMap<PathBuf, CacheEntry> = self
+            .lock()?
+            .clone()
+            .into_par_iter()
+            // Such files are simply hashed again on the next scan
+            .filter(|(path, _)| path.to_str().is_some())
+            // Keep entries that cannot be checked, e.g. on an unmounted share
+            .filter(|(path, _)| !matches!(path.try_exists(), Ok(false)))
+            .collect();
+        let data = serde_json::to_vec(&CacheFile {
+            version: CACHE_VERSION,
+            entries,
+        })
+        .map_err(|e| DejaVuError::Cache(e.to_string()))?;
+
+        let mut temp = tempfile::NamedTempFile::new_in(dir)?;
+        temp.write_all(&data)?;
+        temp.persist(&self.path)
+            .map_err(|e| DejaVuError::Cache(format!("Failed to write {}: {}", self.path.display(), e)))?;
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.lock().map(|entries| entries.len()).unwrap_or(0)
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn get_partial_hash(&self, file: &FileInfo, algorithm: ExactHashAlgorithm) -> Option<Vec<u8>> {
+        self.lookup(file, |entry| decode(entry.partial_hashes.get(algorithm.name())))
+    }
+
+    pub fn put_partial_hash(&self, file: &FileInfo, algorithm: ExactHashAlgorithm, hash: &[u8]) {
+        self.update(file, |entry| {
+            entry.partial_hashes.insert(algorithm.name().to_string(), hex::encode(hash));
+        });
+    }
+
+    pub fn get_exact_hash(&self, file: &FileInfo, algorithm: ExactHashAlgorithm) -> Option<Vec<u8>> {
+        self.lookup(file, |entry| decode(entry.exact_hashes.get(algorithm.name())))
+    }
+
+    pub fn put_exact_hash(&self, file: &FileInfo, algorithm: ExactHashAlgorithm, hash: &[u8]) {
+        self.update(file, |entry| {
+            entry.exact_hashes.insert(algorithm.name().to_string(), hex::encode(hash));
+        });
+    }
+
+    pub fn get_perceptual_hash(&self, file: &FileInfo, algorithm: HashAlgorithm) -> Option<u64> {
+        self.lookup(file, |entry| entry.perceptual_hashes.get(algorithm.name()).copied())
+    }
+
+    pub fn put_perceptual_hash(&self, file: &FileInfo, algorithm: HashAlgorithm, hash: u64) {
+        self.update(file, |entry| {
+            entry.perceptual_hashes.insert(algorithm.name().to_string(), hash);
+        });
+    }
+
+    fn lookup<T>(&self, file: &FileInfo, get: impl FnOnce(&CacheEntry) -> Option<T>) -> Option<T> {
+        // Resolve the path before locking, so threads do not wait on each
+        // other's filesystem round trips
+        let key = cache_key(&file.path);
+        let value = self.lock().ok().and_then(|entries| {
+            entries
+                .get(&key)
+                .filter(|entry| entry.matches(file))
+                .and_then(get)
+        });
+
+        let counter = if value.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    fn update(&self, file: &FileInfo, set: impl FnOnce(&mut CacheEntry)) {
+        let key = cache_key(&file.path);
+        if let Ok(mut entries) = self.lock() {
+            let entry = entries.entry(key).or_default();
+            // Metadata changed: everything cached for the old content is stale
+            if !entry.matches(file) {
+                *entry = CacheEntry::for_file(file);
+            }
+            set(entry);
+        }
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, HashMap<PathBuf, CacheEntry>>> {
+        self.entries
+            .lock()
+            .map_err(|e| DejaVuError::Cache(format!("Mutex poisoned: {}", e)))
+    }
+}
+
+fn decode(hash: Option<&String>) -> Option<Vec<u8>> {
+    hash.and_then(|h| hex::decode(h).ok())
+}
+
+/// Canonical path used as the cache key, falling back to the path as given
+fn cache_key(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn timestamp(time: SystemTime) -> (u64, u32) {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| (d.as_secs(), d.subsec_nanos()))
+        .unwrap_or((0, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::file_info::{ImageFormat, MediaType};
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn file_info(path: &Path, size: u64, modified: SystemTime) -> FileInfo {
+        FileInfo::new(path.to_path_buf(), size, modified, MediaType::Image(ImageFormat::Jpeg))
+    }
+
+    #[test]
+    fn test_hit_requires_matching_metadata() {
+        let dir = TempDir::new().unwrap();
+        let cache = HashCache::empty(&dir.path().join("cache.json"));
+        let modified = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let file = file_info(&dir.path().join("a.jpg"), 100, modified);
+
+        cache.put_exact_hash(&file, ExactHashAlgorithm::Sha256, &[1, 2, 3]);
+        assert_eq!(cache.get_exact_hash(&file, ExactHashAlgorithm::Sha256), Some(vec![1, 2, 3]));
+
+        let touched = file_info(&file.path, 100, modified + Duration::from_secs(1));
+        assert_eq!(cache.get_exact_hash(&touched, ExactHashAlgorithm::Sha256), None);
+
+        let resized = file_info(&file.path, 101, modified);
+        assert_eq!(cache.get_exact_hash(&resized, ExactHashAlgorithm::Sha256), None);
+
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 2 });
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let cache_path = dir.path().join("nested").join("cache.json");
+        let file = file_info(&dir.path().join("a.jpg"), 100, UNIX_EPOCH + Duration::from_nanos(1_500));
+        std::fs::write(&file.path, b"a").unwrap();
+
+        let cache = HashCache::empty(&cache_path);
+        cache.put_partial_hash(&file, ExactHashAlgorithm::Blake3, &[9]);
+        cache.put_exact_hash(&file, ExactHashAlgorithm::Blake3, &[4, 5]);
+        cache.put_perceptual_hash(&file, HashAlgorithm::Difference, 42);
+        cache.save().unwrap();
+
+        let loaded = HashCache::load(&cache_path);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded.get_partial_hash(&file, ExactHashAlgorithm::Blake3), Some(vec![9]));
+        assert_eq!(loaded.get_exact_hash(&file, ExactHashAlgorithm::Blake3), Some(vec![4, 5]));
+        assert_eq!(loaded.get_exact_hash(&file, ExactHashAlgorithm::Sha256), None);
+        assert_eq!(loaded.get_perceptual_hash(&file, HashAlgorithm::Difference), Some(42));
+        assert_eq!(loaded.get_perceptual_hash(&file, HashAlgorithm::Perceptual), None);
+    }
+
+    #[test]
+    fn test_save_drops_missing_files() {
+        let dir = TempDir::new().unwrap();
+        let cache_path = dir.path().join("cache.json");
+        let kept = file_info(&dir.path().join("kept.jpg"), 1, UNIX_EPOCH);
+        let gone = file_info(&dir.path().join("gone.jpg"), 1, UNIX_EPOCH);
+        std::fs::write(&kept.path, b"k").unwrap();
+
+        let cache = HashCache::empty(&cache_path);
+        cache.put_exact_hash(&kept, ExactHashAlgorithm::Sha256, &[1]);
+        cache.put_exact_hash(&gone, ExactHashAlgorithm::Sha256, &[2]);
+        cache.save().unwrap();
+
+        let loaded = HashCache::load(&cache_path);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded.get_exact_hash(&kept, ExactHashAlgorithm::Sha256), Some(vec![1]));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_skips_non_utf8_paths() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = TempDir::new().unwrap();
+        let cache_path = dir.path().join("cache.json");
+        let kept = file_info(&dir.path().join("kept.jpg"), 1, UNIX_EPOCH);
+        let latin1 = file_info(&dir.path().join(std::ffi::OsStr::from_bytes(b"caf\xe9.jpg")), 1, UNIX_EPOCH);
+        std::fs::write(&kept.path, b"k").unwrap();
+        std::fs::write(&latin1.path, b"l").unwrap();
+
+        let cache = HashCache::empty(&cache_path);
+        cache.put_exact_hash(&kept, ExactHashAlgorithm::Sha256, &[1]);
+        cache.put_exact_hash(&latin1, ExactHashAlgorithm::Sha256, &[2]);
+        cache.save().unwrap();
+
+        let loaded = HashCache::load(&cache_path);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded.get_exact_hash(&kept, ExactHashAlgorithm::Sha256), Some(vec![1]));
+    }
+
+    #[test]
+    fn test_corrupt_cache_loads_empty() {
+        let dir = TempDir::new().unwrap();
+        let cache_path = dir.path().join("cache.json");
+        std::fs::write(&cache_path, b"not json").unwrap();
+
+        assert!(HashCache::load(&cache_path).is_empty());
+    }
+}
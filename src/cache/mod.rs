@@ -0,0 +1,3 @@
+pub mod hash_cache;
+
+pub use hash_cache::HashCache;
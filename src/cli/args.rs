@@ -1,14 +1,63 @@
-use clap::Parser;
+use crate::dedup::KeepPolicy;
+use crate::file_ops::{FileAction, LinkKind, SymlinkTarget};
+use crate::hashing::{ExactHashAlgorithm, HashAlgorithm};
+use crate::report::{ReportFormat, ScriptAction};
+use crate::tui::graphics::PreviewMode;
+use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
 /// DejaVu - A TUI duplicate file finder for images and videos
+///
+/// Exit status in report mode: 0 if no duplicates were found, 1 if duplicates
+/// were found, 2 on error.
 #[derive(Parser, Debug, Clone)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, subcommand_negates_reqs = true)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Directory to scan for duplicate files
-    #[arg(value_name = "DIRECTORY")]
-    pub directory: PathBuf,
+    #[arg(value_name = "DIRECTORY", required = true)]
+    pub directory: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub scan: ScanArgs,
+
+    /// Target style for symlinks created in the TUI (relative, absolute)
+    #[arg(long, value_enum, default_value_t = SymlinkTarget::Relative)]
+    pub symlink_target: SymlinkTarget,
+
+    /// Write a report instead of launching the TUI (json, csv, text, html)
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub report: Option<ReportFormat>,
+
+    /// Write a POSIX shell script that applies ACTION to every duplicate except
+    /// the recommended original, instead of launching the TUI (rm, ln,
+    /// symlink, mv); `mv` moves into the `--quarantine` directory
+    #[arg(long, value_enum, value_name = "ACTION", conflicts_with = "report", requires_if("mv", "quarantine"))]
+    pub script: Option<ScriptAction>,
+
+    /// Write the report or script to FILE instead of stdout
+    #[arg(short = 'o', long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// Do not launch the TUI; implies `--report json` unless a format is given
+    #[arg(long)]
+    pub no_tui: bool,
+
+    /// How the TUI previews images (auto, kitty, iterm, sixel, blocks, off)
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = PreviewMode::Auto)]
+    pub preview: PreviewMode,
+
+    /// Only check and journal what deletions, links and moves would do,
+    /// without changing any file
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+}
 
+/// Options controlling what is scanned and how files are grouped
+#[derive(clap::Args, Debug, Clone)]
+pub struct ScanArgs {
     /// Scan images only
     #[arg(short = 'i', long)]
     pub images_only: bool,
@@ -21,7 +70,129 @@ pub struct Args {
     #[arg(short = 't', long, default_value = "5")]
     pub threshold: u32,
 
+    /// Content hash algorithm for exact duplicates (sha256, blake3, xxh3)
+    #[arg(long = "content-hash", value_enum, default_value_t = ExactHashAlgorithm::Sha256)]
+    pub content_hash: ExactHashAlgorithm,
+
+    /// Perceptual hash algorithm for similar images (ahash, dhash, phash, whash)
+    #[arg(long = "hash-algo", value_enum, default_value_t = HashAlgorithm::Perceptual)]
+    pub hash_algo: HashAlgorithm,
+
     /// Minimum file size in bytes (default: 1024)
     #[arg(short = 's', long, default_value = "1024")]
     pub min_size: u64,
+
+    /// Do not read or write the persistent hash cache
+    #[arg(long, conflicts_with = "rebuild_cache")]
+    pub no_cache: bool,
+
+    /// Discard the persistent hash cache and rebuild it from this scan
+    #[arg(long)]
+    pub rebuild_cache: bool,
+
+    /// Move deleted duplicates into DIR, mirroring their original directories,
+    /// instead of the trash
+    #[arg(long, value_name = "DIR")]
+    pub quarantine: Option<PathBuf>,
+}
+
+/// What `dejavu apply` does with the files that are not kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ApplyAction {
+    Trash,
+    /// Delete permanently
+    Delete,
+    /// Move into the `--quarantine` directory
+    Quarantine,
+    HardLink,
+    Reflink,
+    Symlink,
+}
+
+impl ApplyAction {
+    pub fn file_action(&self, symlink_target: SymlinkTarget) -> FileAction {
+        match self {
+            ApplyAction::Trash => FileAction::Trash,
+            ApplyAction::Delete => FileAction::Delete,
+            ApplyAction::Quarantine => FileAction::Quarantine,
+            ApplyAction::HardLink => FileAction::Link(LinkKind::Hard),
+            ApplyAction::Reflink => FileAction::Link(LinkKind::Reflink),
+            ApplyAction::Symlink => FileAction::Link(LinkKind::Symlink(symlink_target)),
+        }
+    }
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Move the most recently trashed batch of files back to their original paths
+    Restore,
+
+    /// List past sessions from the operation journal and the space they freed
+    Log {
+        /// Also list every journaled action
+        #[arg(short, long)]
+        verbose: bool,
+
+        /// Number of most recent sessions to show
+        #[arg(short = 'n', long, default_value = "10")]
+        limit: usize,
+    },
+
+    /// Scan DIRECTORY and act on the duplicates without the TUI, keeping one
+    /// file per group chosen by --keep
+    Apply {
+        /// Directory to scan for duplicate files
+        #[arg(value_name = "DIRECTORY")]
+        directory: PathBuf,
+
+        #[command(flatten)]
+        scan: ScanArgs,
+
+        /// Which file of each group to keep
+        #[arg(long, value_enum)]
+        keep: KeepPolicy,
+
+        /// What to do with the other files of each group
+        #[arg(long, value_enum, requires_if("quarantine", "quarantine"))]
+        action: ApplyAction,
+
+        /// Target style for symlinks (relative, absolute)
+        #[arg(long, value_enum, default_value_t = SymlinkTarget::Relative)]
+        symlink_target: SymlinkTarget,
+
+        /// Also act on groups of similar, not identical, images
+        #[arg(long)]
+        include_similar: bool,
+
+        /// Confirm that the actions should be performed
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+impl Args {
+    /// Report format to produce, if running non-interactively
+    pub fn report_format(&self) -> Option<ReportFormat> {
+        match self.report {
+            Some(format) => Some(format),
+            None if self.script.is_some() => None,
+            None if self.no_tui || self.output.is_some() => Some(ReportFormat::Json),
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_file_needs_output_flag() {
+        let args = Args::try_parse_from(["dejavu", "photos", "--report", "html", "-o", "out.html"]).unwrap();
+        assert_eq!(args.report_format(), Some(ReportFormat::Html));
+        assert_eq!(args.output.as_deref(), Some(std::path::Path::new("out.html")));
+
+        // A second positional is not guessed to be the report file
+        assert!(Args::try_parse_from(["dejavu", "photos", "--report", "html", "out.html"]).is_err());
+    }
 }
@@ -1,2 +1,2 @@
 pub mod args;
-pub use args::Args;
+pub use args::{Args, Command, ScanArgs};
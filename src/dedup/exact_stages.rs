@@ -0,0 +1,186 @@
+//! Staged exact-duplicate detection
+//!
+//! Files only move on to the next, more expensive stage while they still share
+//! a bucket with at least one other file:
+//! 1. Bucket by file size (no I/O)
+//! 2. Hash the first and last `PARTIAL_HASH_BYTES` of each file
+//! 3. Hash the full content
+
+use crate::error::Result;
+use crate::models::FileInfo;
+use indicatif::ProgressBar;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A set of files that are still duplicate candidates, with the key that grouped them
+pub struct Bucket {
+    pub key: Vec<u8>,
+    pub files: Vec<FileInfo>,
+}
+
+/// Number of files that entered each stage of the exact pipeline
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StageCounters {
+    /// Files collected by the scanner
+    pub scanned: usize,
+    /// Files sharing their size with another file
+    pub size_candidates: usize,
+    /// Files sharing their partial hash with another file
+    pub partial_candidates: usize,
+    /// Files whose full content was hashed
+    pub full_hashed: usize,
+    /// Files that ended up in an exact duplicate group
+    pub duplicates: usize,
+}
+
+impl std::fmt::Display for StageCounters {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "size: {} → {} | partial hash: {} | full hash: {} | duplicates: {}",
+            self.scanned,
+            self.size_candidates,
+            self.partial_candidates,
+            self.full_hashed,
+            self.duplicates
+        )
+    }
+}
+
+/// Stage 1: bucket files by size, dropping sizes that only occur once
+pub fn bucket_by_size(files: Vec<FileInfo>) -> Vec<Bucket> {
+    let mut by_size: HashMap<u64, Vec<FileInfo>> = HashMap::new();
+    for file in files {
+        by_size.entry(file.size).or_default().push(file);
+    }
+
+    by_size
+        .into_iter()
+        .filter(|(_, files)| files.len() > 1)
+        .map(|(size, files)| Bucket {
+            key: size.to_le_bytes().to_vec(),
+            files,
+        })
+        .collect()
+}
+
+/// Split every bucket further by `hash`, dropping resulting singletons
+///
+/// Hashes are computed in parallel. Files that cannot be hashed (e.g. removed
+/// or unreadable since the scan) are skipped.
+///
+/// # Arguments
+/// * `buckets` - Candidate buckets from the previous stage
+/// * `stage` - Stage name shown in the progress message
+/// * `progress` - Optional progress bar, reset to the number of files in this stage
+/// * `hash` - Hash function for a single file
+pub fn split_buckets<F>(
+    buckets: Vec<Bucket>,
+    stage: &str,
+    progress: Option<&ProgressBar>,
+    hash: F,
+) -> Vec<Bucket>
+where
+    F: Fn(&FileInfo) -> Result<Vec<u8>> + Sync,
+{
+    let total = count_files(&buckets);
+    let counter = AtomicUsize::new(0);
+
+    if let Some(pb) = progress {
+        pb.set_length(total as u64);
+        pb.set_position(0);
+    }
+
+    let hashed: Vec<Vec<(Option<Vec<u8>>, FileInfo)>> = buckets
+        .into_par_iter()
+        .map(|bucket| {
+            bucket
+                .files
+                .into_par_iter()
+                .map(|file| {
+                    let key = hash(&file).ok();
+
+                    let count = counter.fetch_add(1, Ordering::Relaxed);
+                    if let Some(pb) = progress {
+                        pb.set_message(format!("{}: {} ({} / {})",
+                            stage,
+                            file.filename(),
+                            count + 1,
+                            total
+                        ));
+                        pb.set_position(count as u64 + 1);
+                    }
+
+                    (key, file)
+                })
+                .collect()
+        })
+        .collect();
+
+    hashed
+        .into_iter()
+        .flat_map(|bucket| {
+            let mut by_hash: HashMap<Vec<u8>, Vec<FileInfo>> = HashMap::new();
+            for (key, file) in bucket {
+                if let Some(key) = key {
+                    by_hash.entry(key).or_default().push(file);
+                }
+            }
+            by_hash
+                .into_iter()
+                .filter(|(_, files)| files.len() > 1)
+                .map(|(key, files)| Bucket { key, files })
+        })
+        .collect()
+}
+
+/// Total number of files across all buckets
+pub fn count_files(buckets: &[Bucket]) -> usize {
+    buckets.iter().map(|b| b.files.len()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::file_info::{ImageFormat, MediaType};
+    use std::path::PathBuf;
+    use std::time::SystemTime;
+
+    fn file(name: &str, size: u64) -> FileInfo {
+        FileInfo::new(
+            PathBuf::from(name),
+            size,
+            SystemTime::UNIX_EPOCH,
+            MediaType::Image(ImageFormat::Png),
+        )
+    }
+
+    #[test]
+    fn test_bucket_by_size_drops_unique_sizes() {
+        let buckets = bucket_by_size(vec![file("a", 10), file("b", 10), file("c", 20)]);
+
+        assert_eq!(buckets.len(), 1);
+        assert_eq!(buckets[0].files.len(), 2);
+    }
+
+    #[test]
+    fn test_split_buckets_drops_singletons_and_failures() {
+        let buckets = vec![Bucket {
+            key: Vec::new(),
+            files: vec![file("a1", 10), file("a2", 10), file("b", 10), file("missing", 10)],
+        }];
+
+        let split = split_buckets(buckets, "test", None, |f| {
+            let name = f.path.to_str().unwrap();
+            if name == "missing" {
+                return Err(crate::error::DejaVuError::HashError(name.to_string()));
+            }
+            Ok(name.as_bytes()[..1].to_vec())
+        });
+
+        assert_eq!(split.len(), 1);
+        assert_eq!(split[0].key, b"a");
+        assert_eq!(count_files(&split), 2);
+    }
+}
@@ -2,11 +2,14 @@
 //!
 //! This module provides functionality to group duplicate files using various hashing methods.
 
+use crate::cache::HashCache;
+use crate::dedup::exact_stages::{self, Bucket, StageCounters};
 use crate::error::Result;
-use crate::models::file_info::FileInfo;
-use crate::models::DuplicateGroup;
-use crate::hashing::{ExactHasher, PerceptualHasher};
-use std::collections::HashMap;
+use crate::models::file_info::{FileId, FileInfo};
+use crate::models::{DuplicateGroup, GroupKind};
+use crate::hashing::{ExactHashAlgorithm, ExactHasher, HashAlgorithm, PerceptualHasher};
+use std::collections::HashSet;
+use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use indicatif::ProgressBar;
@@ -15,11 +18,17 @@ use rayon::prelude::*;
 /// Groups duplicate files using hash-based algorithms
 ///
 /// HashGrouper provides two-stage duplicate detection:
-/// 1. Exact duplicates using SHA-256 hashes
-/// 2. Similar images using perceptual hashing (future enhancement)
+/// 1. Exact duplicates using content hashes (SHA-256, BLAKE3 or xxh3) (size -> partial hash -> full hash)
+/// 2. Similar images using perceptual hashing
 pub struct HashGrouper {
-    /// Maximum Hamming distance for perceptual hash similarity (unused in current implementation)
+    /// Maximum Hamming distance for perceptual hash similarity
     similarity_threshold: u32,
+    /// Content hasher used for exact duplicates
+    exact_hasher: ExactHasher,
+    /// Perceptual hash algorithm used for similar images
+    hash_algorithm: HashAlgorithm,
+    /// Optional persistent cache consulted before hashing a file
+    cache: Option<Arc<HashCache>>,
 }
 
 impl HashGrouper {
@@ -28,14 +37,76 @@ impl HashGrouper {
     /// # Arguments
     /// * `similarity_threshold` - Maximum Hamming distance for similar images (lower = stricter)
     pub fn new(similarity_threshold: u32) -> Self {
-        Self { similarity_threshold }
+        Self {
+            similarity_threshold,
+            exact_hasher: ExactHasher::default(),
+            hash_algorithm: HashAlgorithm::default(),
+            cache: None,
+        }
+    }
+
+    /// Use the given content hash algorithm for exact duplicate detection
+    pub fn with_exact_hash_algorithm(mut self, algorithm: ExactHashAlgorithm) -> Self {
+        self.exact_hasher = ExactHasher::new(algorithm);
+        self
+    }
+
+    /// Use the given perceptual hash algorithm for similar image detection
+    pub fn with_hash_algorithm(mut self, hash_algorithm: HashAlgorithm) -> Self {
+        self.hash_algorithm = hash_algorithm;
+        self
+    }
+
+    /// Reuse and record hashes in the given cache
+    pub fn with_cache(mut self, cache: Arc<HashCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Partial hash of a file, from the cache when its metadata is unchanged
+    fn partial_hash(&self, file: &FileInfo) -> Result<Vec<u8>> {
+        let algorithm = self.exact_hasher.algorithm();
+        if let Some(hash) = self.cache.as_ref().and_then(|c| c.get_partial_hash(file, algorithm)) {
+            return Ok(hash);
+        }
+        let hash = self.exact_hasher.compute_partial_hash(&file.path, file.size)?;
+        if let Some(cache) = &self.cache {
+            cache.put_partial_hash(file, algorithm, &hash);
+        }
+        Ok(hash)
+    }
+
+    /// Full content hash of a file, from the cache when its metadata is unchanged
+    fn exact_hash(&self, file: &FileInfo) -> Result<Vec<u8>> {
+        let algorithm = self.exact_hasher.algorithm();
+        if let Some(hash) = self.cache.as_ref().and_then(|c| c.get_exact_hash(file, algorithm)) {
+            return Ok(hash);
+        }
+        let hash = self.exact_hasher.compute_hash(&file.path)?;
+        if let Some(cache) = &self.cache {
+            cache.put_exact_hash(file, algorithm, &hash);
+        }
+        Ok(hash)
+    }
+
+    /// Perceptual hash of an image, from the cache when its metadata is unchanged
+    fn perceptual_hash(&self, hasher: &PerceptualHasher, file: &FileInfo) -> Result<u64> {
+        let algorithm = hasher.algorithm();
+        if let Some(hash) = self.cache.as_ref().and_then(|c| c.get_perceptual_hash(file, algorithm)) {
+            return Ok(hash);
+        }
+        let hash = hasher.compute_hash(&file.path)?;
+        if let Some(cache) = &self.cache {
+            cache.put_perceptual_hash(file, algorithm, hash);
+        }
+        Ok(hash)
     }
 
-    /// Group files by exact SHA-256 hash using multi-threading
+    /// Group files by exact content hash using a staged, multi-threaded pipeline
     ///
-    /// This method computes SHA-256 hashes for all files in parallel using rayon,
-    /// then groups files with identical hashes together. Only groups with 2 or more
-    /// files are returned.
+    /// Files are first bucketed by size, then by a hash of their first and last
+    /// `PARTIAL_HASH_BYTES`, and only files still sharing a bucket get a full
+    /// content hash. Only groups with 2 or more files are returned.
     ///
     /// # Arguments
     /// * `files` - Vector of files to group
@@ -45,60 +116,62 @@ impl HashGrouper {
     /// Vector of DuplicateGroup containing only groups with duplicates
     ///
     /// # Performance
-    /// Uses multiple CPU cores to compute hashes in parallel, significantly
-    /// reducing processing time for large file collections.
+    /// Files with a unique size are never read, and files with a unique
+    /// partial hash are only read at both ends. Hashing within each stage
+    /// uses multiple CPU cores.
     pub fn group_by_exact_hash(&self, files: Vec<FileInfo>, progress: Option<&ProgressBar>) -> Result<Vec<DuplicateGroup>> {
-        use std::sync::Mutex;
-
-        let hash_map: HashMap<Vec<u8>, Vec<FileInfo>> = HashMap::new();
-        let hash_map = Arc::new(Mutex::new(hash_map));
-        let counter = Arc::new(AtomicUsize::new(0));
-
-        // Process files in parallel
-        files.par_iter().for_each(|file| {
-            // Compute hash for this file
-            if let Ok(hash) = ExactHasher::compute_hash(&file.path) {
-                // Insert into hash map
-                if let Ok(mut map) = hash_map.lock() {
-                    map.entry(hash).or_default().push(file.clone());
-                }
-            }
+        let mut counters = StageCounters {
+            scanned: files.len(),
+            ..Default::default()
+        };
 
-            // Update progress
-            let count = counter.fetch_add(1, Ordering::Relaxed);
-            if let Some(pb) = progress {
-                pb.set_message(format!("Hashing: {} ({} / {})",
-                    file.filename(),
-                    count + 1,
-                    files.len()
-                ));
-                pb.set_position(count as u64 + 1);
-            }
-        });
-
-        // Extract the collected results
-        let hash_map = Arc::try_unwrap(hash_map)
-            .expect("Arc should have only one reference left")
-            .into_inner()
-            .map_err(|e| crate::error::DejaVuError::FileOperationFailed(
-                format!("Mutex poisoned: {}", e)
-            ))?;
-
-        // Filter to only groups with duplicates
-        let groups: Vec<DuplicateGroup> = hash_map
+        // Stage 1: size
+        let size_buckets = exact_stages::bucket_by_size(files);
+        counters.size_candidates = exact_stages::count_files(&size_buckets);
+
+        // Stage 2: first/last bytes
+        let partial_buckets = exact_stages::split_buckets(
+            size_buckets,
+            &format!("Partial hash [{} / {} same size]", counters.size_candidates, counters.scanned),
+            progress,
+            |file| self.partial_hash(file),
+        );
+        counters.partial_candidates = exact_stages::count_files(&partial_buckets);
+
+        // Small files were hashed in full by the partial stage already
+        let (complete, partial_only): (Vec<Bucket>, Vec<Bucket>) = partial_buckets
+            .into_iter()
+            .partition(|b| ExactHasher::partial_covers_file(b.files[0].size));
+
+        // Stage 3: full content
+        counters.full_hashed = exact_stages::count_files(&partial_only);
+        let full_buckets = exact_stages::split_buckets(
+            partial_only,
+            &format!("Full hash [{} / {} same partial hash]", counters.full_hashed, counters.partial_candidates),
+            progress,
+            |file| self.exact_hash(file),
+        );
+
+        let groups: Vec<DuplicateGroup> = complete
             .into_iter()
-            .filter(|(_, files)| files.len() > 1)
+            .chain(full_buckets)
             .enumerate()
-            .map(|(i, (hash, files))| DuplicateGroup::new(i, files).with_exact_hash(hash))
+            .map(|(i, bucket)| DuplicateGroup::new(i, bucket.files).with_exact_hash(bucket.key, self.exact_hasher.algorithm()))
             .collect();
 
+        counters.duplicates = groups.iter().map(|g| g.file_count()).sum();
+        if let Some(pb) = progress {
+            pb.println(format!("  {}", counters));
+        }
+
         Ok(groups)
     }
 
     /// Find similar images using perceptual hashing
     ///
-    /// This method computes perceptual hashes for images and groups them
-    /// based on Hamming distance. Files with Hamming distance below the
+    /// This method computes perceptual hashes for images in parallel, using the
+    /// configured algorithm, and groups
+    /// them based on Hamming distance. Files with Hamming distance below the
     /// threshold are considered similar.
     ///
     /// # Arguments
@@ -106,37 +179,47 @@ impl HashGrouper {
     /// * `progress` - Optional progress bar for status updates
     ///
     /// # Returns
-    /// Vector of DuplicateGroup containing groups of similar images
+    /// Vector of DuplicateGroup tagged as `GroupKind::Similar`
     ///
     /// # Note
-    /// This method currently uses a simplified perceptual hash implementation.
-    /// Video files are assigned a dummy hash and are not grouped.
+    /// Videos and images that cannot be decoded are skipped.
     pub fn find_similar_images(&self, files: Vec<FileInfo>, progress: Option<&ProgressBar>) -> Result<Vec<DuplicateGroup>> {
-        let perceptual_hasher = PerceptualHasher::new();
-        let mut perceptual_hashes: Vec<u64> = Vec::with_capacity(files.len());
+        let perceptual_hasher = PerceptualHasher::new().with_algorithm(self.hash_algorithm);
+        let counter = AtomicUsize::new(0);
 
         // Compute perceptual hashes for all images
-        for (index, file) in files.iter().enumerate() {
-            if let Some(pb) = progress {
-                pb.set_message(format!("Computing perceptual hash: {}", file.filename()));
-                pb.set_position(index as u64);
-            }
+        let perceptual_hashes: Vec<Option<u64>> = files
+            .par_iter()
+            .map(|file| {
+                let hash = if file.is_image() {
+                    self.perceptual_hash(&perceptual_hasher, file).ok()
+                } else {
+                    None
+                };
 
-            if file.is_image() {
-                let hash = perceptual_hasher.compute_hash(&file.path)?;
-                perceptual_hashes.push(hash);
-            } else {
-                // For videos, use a dummy hash (not supported yet)
-                perceptual_hashes.push(0);
-            }
-        }
+                let count = counter.fetch_add(1, Ordering::Relaxed);
+                if let Some(pb) = progress {
+                    pb.set_message(format!("Computing perceptual hash: {} ({} / {})",
+                        file.filename(),
+                        count + 1,
+                        files.len()
+                    ));
+                    pb.set_position(count as u64 + 1);
+                }
+
+                hash
+            })
+            .collect();
 
         // Group similar images
         let mut groups: Vec<DuplicateGroup> = Vec::new();
         let mut assigned = vec![false; files.len()];
 
         for i in 0..files.len() {
-            if assigned[i] || !files[i].is_image() {
+            let Some(hash_i) = perceptual_hashes[i] else {
+                continue;
+            };
+            if assigned[i] {
                 continue;
             }
 
@@ -145,15 +228,14 @@ impl HashGrouper {
 
             // Find all similar images
             for j in (i + 1)..files.len() {
-                if assigned[j] || !files[j].is_image() {
+                let Some(hash_j) = perceptual_hashes[j] else {
+                    continue;
+                };
+                if assigned[j] {
                     continue;
                 }
 
-                if PerceptualHasher::are_similar(
-                    perceptual_hashes[i],
-                    perceptual_hashes[j],
-                    self.similarity_threshold,
-                ) {
+                if PerceptualHasher::are_similar(hash_i, hash_j, self.similarity_threshold) {
                     similar_files.push(files[j].clone());
                     assigned[j] = true;
                 }
@@ -162,7 +244,8 @@ impl HashGrouper {
             // Only add if there are duplicates
             if similar_files.len() > 1 {
                 let group = DuplicateGroup::new(groups.len(), similar_files)
-                    .with_perceptual_hash(perceptual_hashes[i]);
+                    .with_kind(GroupKind::Similar)
+                    .with_perceptual_hash(hash_i, self.hash_algorithm);
                 groups.push(group);
             }
         }
@@ -172,29 +255,156 @@ impl HashGrouper {
 
     /// Two-stage duplicate detection: exact hash + perceptual hash
     ///
-    /// This is the main entry point for duplicate detection. Currently,
-    /// it only performs exact duplicate detection using SHA-256 hashes
-    /// with multi-threading for improved performance.
-    /// Perceptual hashing for similar images can be added as a second stage.
+    /// This is the main entry point for duplicate detection:
+    /// 1. Files are grouped by exact content hash (multi-threaded)
+    /// 2. Images that are not exact duplicates, plus the recommended original
+    ///    of every exact group, are grouped by perceptual hash
+    ///
+    /// An exact group's representative can therefore also appear in a similar
+    /// group, linking the whole exact group to its visually similar copies.
     ///
     /// # Arguments
     /// * `files` - Vector of files to analyze
     /// * `progress` - Optional progress bar for status updates
     ///
     /// # Returns
-    /// Vector of DuplicateGroup containing all duplicate groups found
+    /// Vector of DuplicateGroup containing exact groups followed by similar groups,
+    /// with sequential group ids. Every input file gets its own `FileId`, shared
+    /// by its copies in different groups. Grouped images carry their dimensions.
     ///
     /// # Performance
     /// Uses rayon for parallel hash computation, automatically utilizing
     /// all available CPU cores for significant speedup on multi-core systems.
-    pub fn find_duplicates(&self, files: Vec<FileInfo>, progress: Option<&ProgressBar>) -> Result<Vec<DuplicateGroup>> {
+    pub fn find_duplicates(&self, mut files: Vec<FileInfo>, progress: Option<&ProgressBar>) -> Result<Vec<DuplicateGroup>> {
+        for (i, file) in files.iter_mut().enumerate() {
+            file.id = FileId(i);
+        }
+
         // Stage 1: Group by exact hash (multi-threaded)
-        let exact_groups = self.group_by_exact_hash(files, progress)?;
+        let exact_groups = self.group_by_exact_hash(files.clone(), progress)?;
+
+        // Stage 2: Perceptual grouping over the remaining images and exact-group representatives
+        let in_exact_group: HashSet<&Path> = exact_groups
+            .iter()
+            .flat_map(|g| g.files.iter().map(|f| f.path.as_path()))
+            .collect();
+
+        let mut candidates: Vec<FileInfo> = exact_groups
+            .iter()
+            .filter_map(|g| g.files.get(g.recommended_original))
+            .filter(|f| f.is_image())
+            .cloned()
+            .collect();
+        candidates.extend(
+            files
+                .iter()
+                .filter(|f| f.is_image() && !in_exact_group.contains(f.path.as_path()))
+                .cloned(),
+        );
+
+        if let Some(pb) = progress {
+            pb.set_length(candidates.len() as u64);
+            pb.set_position(0);
+        }
+        let similar_groups = self.find_similar_images(candidates, progress)?;
+
+        let mut groups: Vec<DuplicateGroup> = exact_groups
+            .into_iter()
+            .chain(similar_groups)
+            .enumerate()
+            .map(|(i, mut group)| {
+                group.group_id = i;
+                group
+            })
+            .collect();
+        read_dimensions(&mut groups);
+
+        Ok(groups)
+    }
+}
+
+/// Fill in the dimensions of grouped images from their headers; images whose
+/// header cannot be read keep `None`
+fn read_dimensions(groups: &mut [DuplicateGroup]) {
+    groups
+        .par_iter_mut()
+        .flat_map(|group| group.files.par_iter_mut())
+        .filter(|file| file.is_image() && file.dimensions.is_none())
+        .for_each(|file| file.dimensions = image::image_dimensions(&file.path).ok());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::file_info::{ImageFormat, MediaType};
+    use image::{Rgb, RgbImage};
+    use tempfile::TempDir;
+
+    fn scene(width: u32, height: u32) -> RgbImage {
+        RgbImage::from_fn(width, height, |x, y| {
+            let fx = x as f32 / width as f32;
+            let fy = y as f32 / height as f32;
+            if (fx - 0.3).powi(2) + (fy - 0.4).powi(2) < 0.04 {
+                Rgb([220, 40, 40])
+            } else if fy > 0.7 {
+                Rgb([40, 120, 40])
+            } else {
+                Rgb([60, 100, (150.0 + 100.0 * fy) as u8])
+            }
+        })
+    }
+
+    fn stripes(width: u32, height: u32) -> RgbImage {
+        RgbImage::from_fn(width, height, |x, _| {
+            if (x / 16) % 2 == 0 { Rgb([255, 255, 255]) } else { Rgb([0, 0, 0]) }
+        })
+    }
+
+    fn image_info(path: &Path) -> FileInfo {
+        let metadata = std::fs::metadata(path).unwrap();
+        FileInfo::new(
+            path.to_path_buf(),
+            metadata.len(),
+            metadata.modified().unwrap(),
+            MediaType::Image(ImageFormat::Png),
+        )
+    }
+
+    #[test]
+    fn test_find_duplicates_tags_exact_and_similar_groups() {
+        let dir = TempDir::new().unwrap();
+        let original = dir.path().join("original.png");
+        let copy = dir.path().join("copy.png");
+        let resized = dir.path().join("resized.png");
+        let unrelated = dir.path().join("unrelated.png");
+
+        scene(256, 256).save(&original).unwrap();
+        std::fs::copy(&original, &copy).unwrap();
+        scene(128, 128).save(&resized).unwrap();
+        stripes(256, 256).save(&unrelated).unwrap();
+
+        let files = [&original, &copy, &resized, &unrelated]
+            .iter()
+            .map(|p| image_info(p))
+            .collect();
+
+        let groups = HashGrouper::new(5).find_duplicates(files, None).unwrap();
 
-        // Stage 2: Find similar images within each group and across groups
-        // For now, we just return exact duplicates
-        // Perceptual hashing can be added as a second pass
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].kind, GroupKind::Exact);
+        assert_eq!(groups[0].file_count(), 2);
+        assert_eq!(groups[1].kind, GroupKind::Similar);
+        assert!(groups[1].files.iter().any(|f| f.path == resized));
+        assert!(groups[0].files.iter().all(|f| f.dimensions == Some((256, 256))));
+        let resized_info = groups[1].files.iter().find(|f| f.path == resized).unwrap();
+        assert_eq!(resized_info.dimensions, Some((128, 128)));
+        assert!(groups.iter().all(|g| g.files.iter().all(|f| f.path != unrelated)));
+        assert_eq!(groups[1].group_id, 1);
 
-        Ok(exact_groups)
+        // The exact group's representative is the same file in both groups
+        let representative = &groups[0].files[groups[0].recommended_original];
+        let again = groups[1].files.iter().find(|f| f.path == representative.path).unwrap();
+        assert_eq!(again.id, representative.id);
+        assert_ne!(groups[0].files[0].id, groups[0].files[1].id);
     }
 }
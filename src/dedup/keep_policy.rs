@@ -0,0 +1,161 @@
+//! Automatic choice of the file to keep in each group
+//!
+//! Ties are broken like the default recommendation: older files first, then
+//! shorter paths.
+
+use crate::models::{DuplicateGroup, FileId, FileInfo};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum KeepPolicy {
+    /// Earliest modification time
+    Oldest,
+    /// Latest modification time
+    Newest,
+    /// Shortest path
+    ShortestPath,
+    /// Largest file
+    Largest,
+    /// Most pixels; files whose dimensions cannot be read rank last
+    HighestResolution,
+}
+
+impl KeepPolicy {
+    /// Index of the file to keep among `files`
+    pub fn select(&self, files: &[FileInfo]) -> usize {
+        let best = match self {
+            KeepPolicy::Oldest => min_index(files, |f| (f.modified, tie_break(f))),
+            KeepPolicy::Newest => min_index(files, |f| (Reverse(f.modified), tie_break(f))),
+            KeepPolicy::ShortestPath => min_index(files, |f| (path_len(f), f.modified)),
+            KeepPolicy::Largest => min_index(files, |f| (Reverse(f.size), f.modified, path_len(f))),
+            KeepPolicy::HighestResolution => {
+                min_index(files, |f| (Reverse(pixels(f)), f.modified, path_len(f)))
+            }
+        };
+        best.unwrap_or(0)
+    }
+
+    /// Make the selected file `group`'s recommended original
+    pub fn apply(&self, group: &mut DuplicateGroup) {
+        group.recommended_original = self.select(&group.files);
+    }
+}
+
+/// Files to act on once every group keeps its recommended original, as
+/// (group index, file index)
+///
+/// A file can be in an exact group and a similar group at once. It is listed
+/// only once, and not at all if any group keeps it.
+pub fn removal_locations(groups: &[DuplicateGroup]) -> Vec<(usize, usize)> {
+    let kept: HashSet<FileId> = groups
+        .iter()
+        .filter_map(|group| group.files.get(group.recommended_original))
+        .map(|file| file.id)
+        .collect();
+
+    let mut seen = HashSet::new();
+    let mut locations = Vec::new();
+    for (group_idx, group) in groups.iter().enumerate() {
+        for (file_idx, file) in group.files.iter().enumerate() {
+            if !kept.contains(&file.id) && seen.insert(file.id) {
+                locations.push((group_idx, file_idx));
+            }
+        }
+    }
+    locations
+}
+
+fn min_index<K: Ord>(files: &[FileInfo], key: impl Fn(&FileInfo) -> K) -> Option<usize> {
+    files
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, f)| key(f))
+        .map(|(i, _)| i)
+}
+
+fn tie_break(file: &FileInfo) -> (usize, SystemTime) {
+    (path_len(file), file.modified)
+}
+
+fn path_len(file: &FileInfo) -> usize {
+    file.path.as_os_str().len()
+}
+
+/// Pixel count from the scan, or read from the image header
+fn pixels(file: &FileInfo) -> u64 {
+    file.dimensions
+        .or_else(|| {
+            if file.is_image() {
+                image::image_dimensions(&file.path).ok()
+            } else {
+                None
+            }
+        })
+        .map(|(width, height)| width as u64 * height as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::file_info::{ImageFormat, MediaType};
+    use std::path::PathBuf;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn file(path: &str, size: u64, modified_secs: u64, dimensions: Option<(u32, u32)>) -> FileInfo {
+        let mut file = FileInfo::new(
+            PathBuf::from(path),
+            size,
+            UNIX_EPOCH + Duration::from_secs(modified_secs),
+            MediaType::Image(ImageFormat::Jpeg),
+        );
+        file.dimensions = dimensions;
+        file
+    }
+
+    #[test]
+    fn test_policies() {
+        let files = vec![
+            file("/photos/archive/2019/a.jpg", 100, 20, Some((800, 600))),
+            file("/photos/b.jpg", 300, 30, Some((1600, 1200))),
+            file("/photos/copies/c.jpg", 200, 10, None),
+        ];
+
+        assert_eq!(KeepPolicy::Oldest.select(&files), 2);
+        assert_eq!(KeepPolicy::Newest.select(&files), 1);
+        assert_eq!(KeepPolicy::ShortestPath.select(&files), 1);
+        assert_eq!(KeepPolicy::Largest.select(&files), 1);
+        assert_eq!(KeepPolicy::HighestResolution.select(&files), 1);
+    }
+
+    #[test]
+    fn test_ties_prefer_older_then_shorter() {
+        let files = vec![
+            file("/photos/long/name/a.jpg", 100, 20, None),
+            file("/photos/a.jpg", 100, 20, None),
+            file("/p/a.jpg", 100, 30, None),
+        ];
+
+        assert_eq!(KeepPolicy::Largest.select(&files), 1);
+        assert_eq!(KeepPolicy::Oldest.select(&files), 1);
+        assert_eq!(KeepPolicy::HighestResolution.select(&files), 1);
+    }
+
+    #[test]
+    fn test_removal_locations_across_overlapping_groups() {
+        let with_id = |path: &str, id: usize| {
+            let mut file = FileInfo::sample(path, 0);
+            file.id = FileId(id);
+            file
+        };
+        // b is a duplicate in both groups; c is kept by the similar group
+        let mut exact = DuplicateGroup::new(0, vec![with_id("/a.jpg", 0), with_id("/b.jpg", 1), with_id("/c.jpg", 2)]);
+        let mut similar = DuplicateGroup::new(1, vec![with_id("/b.jpg", 1), with_id("/c.jpg", 2), with_id("/d.jpg", 3)]);
+        exact.recommended_original = 0;
+        similar.recommended_original = 1;
+
+        assert_eq!(removal_locations(&[exact, similar]), vec![(0, 1), (1, 2)]);
+    }
+}
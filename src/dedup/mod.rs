@@ -1,3 +1,6 @@
+pub mod exact_stages;
 pub mod hash_group;
+pub mod keep_policy;
 
 pub use hash_group::HashGrouper;
+pub use keep_policy::{KeepPolicy, removal_locations};
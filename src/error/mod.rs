@@ -15,16 +15,24 @@ pub enum DejaVuError {
     PathNotFound(String),
 
     #[error("Invalid file type: {0}")]
+    #[allow(dead_code)]
     InvalidFileType(String),
 
     #[error("Hash computation failed: {0}")]
     HashError(String),
 
     #[error("No duplicate files found")]
+    #[allow(dead_code)]
     NoDuplicatesFound,
 
     #[error("File operation failed: {0}")]
     FileOperationFailed(String),
+
+    #[error("Refusing to delete {0}: {1}")]
+    VerificationFailed(String, String),
+
+    #[error("Hash cache error: {0}")]
+    Cache(String),
 }
 
 pub type Result<T> = std::result::Result<T, DejaVuError>;
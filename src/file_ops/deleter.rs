@@ -1,4 +1,9 @@
 use crate::error::Result;
+#[cfg(all(unix, not(target_os = "macos")))]
+use crate::file_ops::trash::XdgTrash;
+use crate::file_ops::restore::TrashedFile;
+use crate::file_ops::verifier::DuplicateVerifier;
+use crate::models::DuplicateGroup;
 use std::path::Path;
 
 pub struct FileDeleter;
@@ -17,9 +22,47 @@ impl FileDeleter {
         Ok(())
     }
 
+    /// Move `group.files[index]` to the trash after verifying it against the file being kept
+    /// Files of exact groups must still match the kept file byte for byte;
+    /// files of similar groups are only re-stat'ed
+    pub fn trash_from_group(group: &DuplicateGroup, index: usize) -> Result<Option<TrashedFile>> {
+        let path = Self::verify_from_group(group, index)?;
+        Self::move_to_trash(path)
+    }
+
+    /// Permanently delete `group.files[index]`, verified like `trash_from_group`
+    pub fn delete_from_group(group: &DuplicateGroup, index: usize) -> Result<()> {
+        let path = Self::verify_from_group(group, index)?;
+        Self::delete(path)
+    }
+
+    /// The checks run before removing `group.files[index]`, without touching it;
+    /// returns the path that would be removed
+    pub fn verify_from_group(group: &DuplicateGroup, index: usize) -> Result<&Path> {
+        let Some(file) = group.files.get(index) else {
+            return Err(crate::error::DejaVuError::FileOperationFailed(format!(
+                "Group #{} has no file #{}",
+                group.group_id + 1,
+                index + 1
+            )));
+        };
+        let Some(original) = group.reference_for(index) else {
+            return Err(crate::error::DejaVuError::VerificationFailed(
+                file.path.display().to_string(),
+                "no other file in the group is kept".to_string(),
+            ));
+        };
+
+        DuplicateVerifier::verify(file, original, group.is_exact_duplicate())?;
+        Ok(&file.path)
+    }
+
     /// Move file to trash (platform-specific)
+    ///
+    /// Returns where the file went when DejaVu manages the trash itself, which
+    /// allows restoring it later; `None` when the system handled it.
     #[cfg(target_os = "macos")]
-    pub fn move_to_trash(path: &Path) -> Result<()> {
+    pub fn move_to_trash(path: &Path) -> Result<Option<TrashedFile>> {
         // macOS: Use osascript to move to trash
         let script = format!(
             "tell application \"Finder\" to delete POSIX file \"{}\"",
@@ -38,39 +81,17 @@ impl FileDeleter {
                 ))
             })?;
 
-        Ok(())
+        Ok(None)
     }
 
-    #[cfg(target_os = "linux")]
-    pub fn move_to_trash(path: &Path) -> Result<()> {
-        // Linux: Use trash-cli if available, otherwise use gio
-        // Try gio first (more common)
-        let result = std::process::Command::new("gio")
-            .arg("trash")
-            .arg(path)
-            .output();
-
-        if result.is_ok() {
-            return Ok(());
-        }
-
-        // Fallback to trash-cli
-        std::process::Command::new("trash-put")
-            .arg(path)
-            .spawn()
-            .map_err(|e| {
-                crate::error::DejaVuError::FileOperationFailed(format!(
-                    "Failed to move to trash {}. Please install 'trash-cli' or ensure gio is available: {}",
-                    path.display(),
-                    e
-                ))
-            })?;
-
-        Ok(())
+    /// Linux and other XDG desktops: in-process Freedesktop.org trash
+    #[cfg(all(unix, not(target_os = "macos")))]
+    pub fn move_to_trash(path: &Path) -> Result<Option<TrashedFile>> {
+        XdgTrash::from_env()?.trash(path).map(Some)
     }
 
     #[cfg(target_os = "windows")]
-    pub fn move_to_trash(path: &Path) -> Result<()> {
+    pub fn move_to_trash(path: &Path) -> Result<Option<TrashedFile>> {
         // Windows: Use PowerShell to move to recycle bin
         let script = format!(
             "Add-Type -AssemblyName System.Windows.Forms; [Windows.Forms.SendKeys]::SendWait('{{ENTER}}'); $shell = New-Object -ComObject Shell.Application; $item = $shell.Namespace(0).ParseName('{}'); $item.InvokeVerb('delete')",
@@ -89,10 +110,11 @@ impl FileDeleter {
                 ))
             })?;
 
-        Ok(())
+        Ok(None)
     }
 
     /// Delete multiple files with confirmation
+    #[allow(dead_code)]
     pub fn delete_multiple(paths: &[&Path]) -> Result<Vec<String>> {
         let mut deleted = Vec::new();
         let mut failed = Vec::new();
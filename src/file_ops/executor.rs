@@ -0,0 +1,395 @@
+//! Journaled file actions
+//!
+//! Front end for the destructive operations in this module: performs one
+//! action on a file of a duplicate group and appends the result, success or
+//! failure, to the operation journal. In a dry run the same checks run but
+//! nothing is changed, and the journal records what would have happened.
+//! Files a dry run would have removed count as gone for the actions after it,
+//! as they would be in a real run.
+
+use crate::error::{DejaVuError, Result};
+use crate::file_ops::journal::{Journal, JournalAction, JournalEntry, Outcome};
+use crate::file_ops::restore::{RestoreReport, TrashedFile};
+use crate::file_ops::verifier::VerificationFailure;
+use crate::file_ops::{FileDeleter, FileLinker, LinkKind, Quarantine};
+use crate::models::DuplicateGroup;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// What to do with a duplicate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileAction {
+    /// Remove permanently
+    Delete,
+    Trash,
+    /// Move into the executor's quarantine directory
+    Quarantine,
+    Link(LinkKind),
+}
+
+impl FileAction {
+    pub fn journal_action(&self) -> JournalAction {
+        match self {
+            FileAction::Delete => JournalAction::Delete,
+            FileAction::Trash => JournalAction::Trash,
+            FileAction::Quarantine => JournalAction::Quarantine,
+            FileAction::Link(LinkKind::Hard) => JournalAction::HardLink,
+            FileAction::Link(LinkKind::Reflink) => JournalAction::Reflink,
+            FileAction::Link(LinkKind::Symlink(_)) => JournalAction::Symlink,
+        }
+    }
+}
+
+/// Result of a successful action
+#[derive(Debug, Default)]
+pub struct ActionOutcome {
+    /// Bytes no longer taken up by the file
+    pub freed_bytes: u64,
+    /// Set when the file went to a trash DejaVu can restore from
+    pub trashed: Option<TrashedFile>,
+    /// The action succeeded but could not be journaled
+    pub journal_error: Option<DejaVuError>,
+    /// The file was quarantined but is missing from the manifest
+    pub manifest_error: Option<DejaVuError>,
+}
+
+/// Files affected by the actions of one executor
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ActionSummary {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+#[derive(Default)]
+pub struct ActionExecutor {
+    journal: Option<Journal>,
+    quarantine: Option<Quarantine>,
+    dry_run: bool,
+    summary: Mutex<ActionSummary>,
+    /// Files removed by earlier actions of a dry run
+    simulated_removals: Mutex<HashSet<PathBuf>>,
+}
+
+impl ActionExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_journal(mut self, journal: Journal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    pub fn with_quarantine(mut self, quarantine: Quarantine) -> Self {
+        self.quarantine = Some(quarantine);
+        self
+    }
+
+    /// Only check and journal actions instead of performing them
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Files and bytes affected so far (or that would have been, in a dry run)
+    pub fn summary(&self) -> ActionSummary {
+        self.summary.lock().map(|s| *s).unwrap_or_default()
+    }
+
+    /// Apply `action` to `group.files[index]` and journal the outcome
+    ///
+    /// Failures of the action itself are journaled and returned.
+    pub fn execute(&self, group: &DuplicateGroup, index: usize, action: FileAction) -> Result<ActionOutcome> {
+        let Some(file) = group.files.get(index) else {
+            return Err(DejaVuError::FileOperationFailed(format!(
+                "Group #{} has no file #{}",
+                group.group_id + 1,
+                index + 1
+            )));
+        };
+
+        let mut entry = JournalEntry::new(action.journal_action(), &file.path, file.size);
+        entry.hash = group.hash_string().map(|hash| match group.algorithm_name() {
+            Some(algorithm) => format!("{}:{}", algorithm, hash),
+            None => hash,
+        });
+        entry.kept = group
+            .reference_for(index)
+            .map(|f| std::path::absolute(&f.path).unwrap_or_else(|_| f.path.clone()));
+
+        let result = if self.dry_run {
+            self.simulate(group, index, action)
+        } else {
+            self.perform(group, index, action)
+        };
+        match &result {
+            Ok((outcome, destination)) => {
+                entry.freed_bytes = outcome.freed_bytes;
+                entry.destination = destination.clone();
+                if self.dry_run {
+                    entry.outcome = Outcome::DryRun;
+                }
+                if let Ok(mut summary) = self.summary.lock() {
+                    summary.files += 1;
+                    summary.bytes += outcome.freed_bytes;
+                }
+            }
+            Err(e) => {
+                entry.outcome = Outcome::Failed;
+                entry.error = Some(e.to_string());
+            }
+        }
+
+        let journaled = self.record(entry);
+        let (mut outcome, _) = result?;
+        outcome.journal_error = journaled.err();
+        Ok(outcome)
+    }
+
+    /// Journal the files an undo moved back, and the ones it could not
+    ///
+    /// An entry that cannot be journaled does not keep the others out; the
+    /// first such error is returned.
+    pub fn record_restore(&self, report: &RestoreReport) -> Result<()> {
+        let failed = report
+            .conflicts
+            .iter()
+            .map(|f| (f, "original path is occupied".to_string()))
+            .chain(report.missing.iter().map(|f| (f, "no longer in the trash".to_string())))
+            .chain(report.failed.iter().map(|(f, e)| (f, e.clone())));
+
+        let restored = report.restored.iter().map(|file| {
+            let size = std::fs::metadata(&file.original_path).map(|m| m.len()).unwrap_or(0);
+            let mut entry = JournalEntry::new(JournalAction::Restore, &file.trashed_path, size);
+            entry.destination = Some(file.original_path.clone());
+            entry
+        });
+        let failed = failed.map(|(file, error)| {
+            let mut entry = JournalEntry::new(JournalAction::Restore, &file.trashed_path, 0);
+            entry.destination = Some(file.original_path.clone());
+            entry.outcome = Outcome::Failed;
+            entry.error = Some(error);
+            entry
+        });
+
+        let mut first_error = None;
+        for entry in restored.chain(failed) {
+            if let Err(e) = self.record(entry) {
+                first_error.get_or_insert(e);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Run the checks `perform` would, without changing anything
+    fn simulate(
+        &self,
+        group: &DuplicateGroup,
+        index: usize,
+        action: FileAction,
+    ) -> Result<(ActionOutcome, Option<PathBuf>)> {
+        let mut removed = self
+            .simulated_removals
+            .lock()
+            .map_err(|e| DejaVuError::FileOperationFailed(format!("Dry run mutex poisoned: {}", e)))?;
+        let file = &group.files[index];
+        // The file or the copy kept in its place is already gone in this run
+        if let Some(gone) = [Some(file), group.reference_for(index)]
+            .into_iter()
+            .flatten()
+            .find(|f| removed.contains(&f.path))
+        {
+            let reason = VerificationFailure::Vanished(gone.path.display().to_string());
+            return Err(DejaVuError::VerificationFailed(file.path.display().to_string(), reason.to_string()));
+        }
+
+        let freed_bytes = match action {
+            FileAction::Quarantine if self.quarantine.is_none() => return Err(no_quarantine()),
+            FileAction::Delete | FileAction::Trash | FileAction::Quarantine => {
+                FileDeleter::verify_from_group(group, index)?;
+                removed.insert(file.path.clone());
+                file.size
+            }
+            FileAction::Link(kind) => FileLinker::check_from_group(group, index, kind)?,
+        };
+
+        let outcome = ActionOutcome {
+            freed_bytes,
+            ..Default::default()
+        };
+        Ok((outcome, None))
+    }
+
+    fn perform(
+        &self,
+        group: &DuplicateGroup,
+        index: usize,
+        action: FileAction,
+    ) -> Result<(ActionOutcome, Option<PathBuf>)> {
+        let size = group.files[index].size;
+        let mut outcome = ActionOutcome::default();
+        let mut destination = None;
+
+        match action {
+            FileAction::Delete => {
+                FileDeleter::delete_from_group(group, index)?;
+                outcome.freed_bytes = size;
+            }
+            FileAction::Trash => {
+                outcome.trashed = FileDeleter::trash_from_group(group, index)?;
+                destination = outcome.trashed.as_ref().map(|f| f.trashed_path.clone());
+                outcome.freed_bytes = size;
+            }
+            FileAction::Quarantine => {
+                let quarantine = self.quarantine.as_ref().ok_or_else(no_quarantine)?;
+                let entry = quarantine.move_from_group(group, index)?;
+                outcome.manifest_error = quarantine.record(&entry).err();
+                destination = Some(entry.quarantined_path);
+                outcome.freed_bytes = size;
+            }
+            FileAction::Link(kind) => {
+                outcome.freed_bytes = FileLinker::link_from_group(group, index, kind)?;
+            }
+        }
+
+        Ok((outcome, destination))
+    }
+
+    fn record(&self, entry: JournalEntry) -> Result<()> {
+        match &self.journal {
+            Some(journal) => journal.record(entry),
+            None => Ok(()),
+        }
+    }
+}
+
+fn no_quarantine() -> DejaVuError {
+    DejaVuError::FileOperationFailed("No quarantine directory configured".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::FileInfo;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_successes_and_failures_are_journaled() {
+        let dir = TempDir::new().unwrap();
+        let journal_path = dir.path().join("journal.jsonl");
+        let kept = dir.path().join("a.jpg");
+        let duplicate = dir.path().join("b.jpg");
+        fs::write(&kept, b"same bytes").unwrap();
+        fs::write(&duplicate, b"same bytes").unwrap();
+
+        let executor = ActionExecutor::new()
+            .with_journal(Journal::open(&journal_path).unwrap())
+            .with_quarantine(Quarantine::new(dir.path().join("quarantine")));
+        let group = DuplicateGroup::new(0, vec![FileInfo::scanned(&kept), FileInfo::scanned(&duplicate)]);
+
+        let outcome = executor.execute(&group, 1, FileAction::Quarantine).unwrap();
+        assert_eq!(outcome.freed_bytes, 10);
+        assert!(outcome.journal_error.is_none());
+        assert!(!duplicate.exists());
+
+        // The duplicate is gone now, so deleting it again must fail
+        assert!(executor.execute(&group, 1, FileAction::Delete).is_err());
+
+        let entries = Journal::read(&journal_path).unwrap().records;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action, JournalAction::Quarantine);
+        assert_eq!(entries[0].outcome, Outcome::Ok);
+        assert_eq!(entries[0].kept.as_deref(), Some(kept.as_path()));
+        assert!(entries[0].destination.as_ref().unwrap().exists());
+        assert_eq!(entries[1].action, JournalAction::Delete);
+        assert_eq!(entries[1].outcome, Outcome::Failed);
+        assert!(entries[1].error.is_some());
+        assert_eq!(entries[1].freed_bytes, 0);
+    }
+
+    #[test]
+    fn test_manifest_failure_does_not_fail_the_move() {
+        let dir = TempDir::new().unwrap();
+        let kept = dir.path().join("a.jpg");
+        let duplicate = dir.path().join("b.jpg");
+        fs::write(&kept, b"same bytes").unwrap();
+        fs::write(&duplicate, b"same bytes").unwrap();
+
+        let quarantine = Quarantine::new(dir.path().join("quarantine"));
+        // A directory where the manifest should be cannot be appended to
+        fs::create_dir_all(quarantine.manifest_path()).unwrap();
+        let executor = ActionExecutor::new().with_quarantine(quarantine);
+        let group = DuplicateGroup::new(0, vec![FileInfo::scanned(&kept), FileInfo::scanned(&duplicate)]);
+
+        let outcome = executor.execute(&group, 1, FileAction::Quarantine).unwrap();
+        assert!(outcome.manifest_error.is_some());
+        assert_eq!(outcome.freed_bytes, 10);
+        assert!(!duplicate.exists());
+        assert_eq!(executor.summary(), ActionSummary { files: 1, bytes: 10 });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unjournalable_restore_entry_is_skipped() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = TempDir::new().unwrap();
+        let journal_path = dir.path().join("journal.jsonl");
+        let executor = ActionExecutor::new().with_journal(Journal::open(&journal_path).unwrap());
+        let trashed = |name: &[u8]| TrashedFile {
+            original_path: dir.path().join(std::ffi::OsStr::from_bytes(name)),
+            trashed_path: dir.path().join("trash").join(std::ffi::OsStr::from_bytes(name)),
+            info_path: dir.path().join("info"),
+        };
+        let report = RestoreReport {
+            conflicts: vec![trashed(b"caf\xe9.jpg"), trashed(b"cafe.jpg")],
+            ..Default::default()
+        };
+
+        assert!(executor.record_restore(&report).is_err());
+        let entries = Journal::read(&journal_path).unwrap().records;
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].path.ends_with("trash/cafe.jpg"));
+    }
+
+    #[test]
+    fn test_dry_run_changes_nothing() {
+        let dir = TempDir::new().unwrap();
+        let journal_path = dir.path().join("journal.jsonl");
+        let kept = dir.path().join("a.jpg");
+        let duplicate = dir.path().join("b.jpg");
+        fs::write(&kept, b"same bytes").unwrap();
+        fs::write(&duplicate, b"same bytes").unwrap();
+
+        let executor = ActionExecutor::new()
+            .with_journal(Journal::open(&journal_path).unwrap())
+            .with_dry_run(true);
+        let group = DuplicateGroup::new(0, vec![FileInfo::scanned(&kept), FileInfo::scanned(&duplicate)]);
+
+        executor.execute(&group, 1, FileAction::Link(LinkKind::Symlink(Default::default()))).unwrap();
+        // Checks still run: there is no quarantine directory to move into
+        assert!(executor.execute(&group, 1, FileAction::Quarantine).is_err());
+        executor.execute(&group, 1, FileAction::Delete).unwrap();
+        // Later actions see the duplicate as gone, like a real run would:
+        // it cannot be removed twice, and the original is now the last copy
+        assert!(executor.execute(&group, 1, FileAction::Trash).is_err());
+        assert!(executor.execute(&group, 0, FileAction::Delete).is_err());
+
+        assert_eq!(fs::read(&duplicate).unwrap(), b"same bytes");
+        assert!(!fs::symlink_metadata(&duplicate).unwrap().file_type().is_symlink());
+        assert_eq!(executor.summary(), ActionSummary { files: 2, bytes: 20 });
+
+        let entries = Journal::read(&journal_path).unwrap().records;
+        let outcomes: Vec<Outcome> = entries.iter().map(|e| e.outcome).collect();
+        assert_eq!(
+            outcomes,
+            [Outcome::DryRun, Outcome::Failed, Outcome::DryRun, Outcome::Failed, Outcome::Failed]
+        );
+    }
+}
@@ -0,0 +1,282 @@
+//! Append-only operation journal
+//!
+//! Every destructive action (delete, trash, link, quarantine, restore) is
+//! appended to `$XDG_DATA_HOME/dejavu/journal.jsonl`, one JSON object per
+//! line, whether it succeeded or not. Entries written by one DejaVu run share
+//! a session id, which `dejavu log` uses to summarize past runs. Paths are
+//! recorded absolute, so the log reads the same from any directory.
+
+use super::json_lines::{self, JsonLines};
+use crate::error::{DejaVuError, Result};
+use chrono::{Local, SecondsFormat};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const JOURNAL_FILE_NAME: &str = "journal.jsonl";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalAction {
+    Delete,
+    Trash,
+    HardLink,
+    Reflink,
+    Symlink,
+    Quarantine,
+    Restore,
+}
+
+impl JournalAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JournalAction::Delete => "delete",
+            JournalAction::Trash => "trash",
+            JournalAction::HardLink => "hard_link",
+            JournalAction::Reflink => "reflink",
+            JournalAction::Symlink => "symlink",
+            JournalAction::Quarantine => "quarantine",
+            JournalAction::Restore => "restore",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Ok,
+    Failed,
+    /// `--dry-run`: the checks passed but nothing was changed
+    DryRun,
+}
+
+/// One line of the journal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub session: String,
+    /// RFC 3339 local time
+    pub timestamp: String,
+    pub action: JournalAction,
+    /// File the action was applied to
+    pub path: PathBuf,
+    pub size: u64,
+    /// Hash shared by the file's duplicate group
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    /// File kept in place of `path`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kept: Option<PathBuf>,
+    /// Where the file was moved (trash, quarantine) or restored to; links
+    /// point at `kept`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination: Option<PathBuf>,
+    pub outcome: Outcome,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Bytes no longer taken up at `path` (or that would be, in a dry run)
+    pub freed_bytes: u64,
+}
+
+impl JournalEntry {
+    /// A successful entry for the current session's clock, with `path` made
+    /// absolute
+    pub fn new(action: JournalAction, path: &Path, size: u64) -> Self {
+        Self {
+            session: String::new(),
+            timestamp: Local::now().to_rfc3339_opts(SecondsFormat::Secs, false),
+            action,
+            path: std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()),
+            size,
+            hash: None,
+            kept: None,
+            destination: None,
+            outcome: Outcome::Ok,
+            error: None,
+            freed_bytes: 0,
+        }
+    }
+}
+
+/// What one session did
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionSummary {
+    pub session: String,
+    pub started_at: String,
+    pub ended_at: String,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Actions only simulated by `--dry-run`
+    pub dry_run: usize,
+    pub freed_bytes: u64,
+    /// Successful actions by name
+    pub actions: BTreeMap<&'static str, usize>,
+}
+
+/// Journal handle for one session
+pub struct Journal {
+    session: String,
+    file: Mutex<File>,
+}
+
+impl Journal {
+    /// Default location: `$XDG_DATA_HOME/dejavu/journal.jsonl`
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("dejavu").join(JOURNAL_FILE_NAME))
+    }
+
+    /// Open the journal for appending and start a new session
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(Self {
+            session: format!("{}-{}", Local::now().format("%Y%m%d-%H%M%S"), std::process::id()),
+            file: Mutex::new(file),
+        })
+    }
+
+    #[cfg(test)]
+    pub fn session(&self) -> &str {
+        &self.session
+    }
+
+    /// Append `entry`, stamped with this session's id
+    pub fn record(&self, mut entry: JournalEntry) -> Result<()> {
+        entry.session = self.session.clone();
+        let line = serde_json::to_string(&entry).map_err(journal_error)?;
+
+        let mut file = self
+            .file
+            .lock()
+            .map_err(|e| DejaVuError::FileOperationFailed(format!("Journal mutex poisoned: {}", e)))?;
+        writeln!(file, "{}", line)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// All entries in `path`, oldest first, skipping lines that are not
+    /// valid entries
+    pub fn read(path: &Path) -> Result<JsonLines<JournalEntry>> {
+        json_lines::read(path)
+    }
+
+    /// Summaries of the sessions in `entries`, in the order they started
+    pub fn sessions(entries: &[JournalEntry]) -> Vec<SessionSummary> {
+        let mut sessions: Vec<SessionSummary> = Vec::new();
+
+        for entry in entries {
+            let index = match sessions.iter().position(|s| s.session == entry.session) {
+                Some(index) => index,
+                None => {
+                    sessions.push(SessionSummary {
+                        session: entry.session.clone(),
+                        started_at: entry.timestamp.clone(),
+                        ..Default::default()
+                    });
+                    sessions.len() - 1
+                }
+            };
+
+            let summary = &mut sessions[index];
+            summary.ended_at = entry.timestamp.clone();
+            match entry.outcome {
+                Outcome::Ok => {
+                    summary.succeeded += 1;
+                    summary.freed_bytes += entry.freed_bytes;
+                    *summary.actions.entry(entry.action.as_str()).or_default() += 1;
+                }
+                Outcome::Failed => summary.failed += 1,
+                Outcome::DryRun => summary.dry_run += 1,
+            }
+        }
+
+        sessions
+    }
+}
+
+fn journal_error(e: serde_json::Error) -> DejaVuError {
+    DejaVuError::FileOperationFailed(format!("Invalid journal entry: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_and_summarize_sessions() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("dejavu").join(JOURNAL_FILE_NAME);
+
+        let first = Journal::open(&path).unwrap();
+        let mut trash = JournalEntry::new(JournalAction::Trash, Path::new("/p/b.jpg"), 100);
+        trash.freed_bytes = 100;
+        trash.hash = Some("abcd".to_string());
+        first.record(trash).unwrap();
+
+        let mut failed = JournalEntry::new(JournalAction::HardLink, Path::new("/p/c.jpg"), 50);
+        failed.outcome = Outcome::Failed;
+        failed.error = Some("changed since the scan".to_string());
+        first.record(failed).unwrap();
+
+        // Sessions started within the same second by the same process share
+        // an id, so tell the second one apart explicitly
+        let mut second = Journal::open(&path).unwrap();
+        second.session.push_str("-b");
+        let mut link = JournalEntry::new(JournalAction::Symlink, Path::new("/p/d.jpg"), 30);
+        link.freed_bytes = 30;
+        second.record(link).unwrap();
+        let mut simulated = JournalEntry::new(JournalAction::Delete, Path::new("/p/e.jpg"), 40);
+        simulated.freed_bytes = 40;
+        simulated.outcome = Outcome::DryRun;
+        second.record(simulated).unwrap();
+
+        let entries = Journal::read(&path).unwrap().records;
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].session, first.session());
+        assert_eq!(entries[0].hash.as_deref(), Some("abcd"));
+
+        let sessions = Journal::sessions(&entries);
+        assert_eq!(sessions.len(), 2);
+        assert_eq!((sessions[0].succeeded, sessions[0].failed, sessions[0].freed_bytes), (1, 1, 100));
+        assert_eq!(sessions[0].actions.get("trash"), Some(&1));
+        assert_eq!((sessions[1].succeeded, sessions[1].dry_run, sessions[1].freed_bytes), (1, 1, 30));
+    }
+
+    #[test]
+    fn test_entry_layout() {
+        let entry = JournalEntry::new(JournalAction::HardLink, Path::new("/p/b.jpg"), 10);
+        let value: serde_json::Value = serde_json::to_value(&entry).unwrap();
+
+        assert_eq!(value["action"], "hard_link");
+        assert_eq!(value["outcome"], "ok");
+        assert!(value.get("error").is_none());
+
+        let relative = JournalEntry::new(JournalAction::Trash, Path::new("dvt/b.jpg"), 10);
+        assert!(relative.path.is_absolute());
+        assert!(relative.path.ends_with("dvt/b.jpg"));
+    }
+
+    #[test]
+    fn test_read_skips_bad_lines() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(JOURNAL_FILE_NAME);
+        let journal = Journal::open(&path).unwrap();
+        journal.record(JournalEntry::new(JournalAction::Trash, Path::new("/p/a.jpg"), 1)).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(file, "not json").unwrap();
+        journal.record(JournalEntry::new(JournalAction::Trash, Path::new("/p/b.jpg"), 2)).unwrap();
+        // Cut short by a crash
+        write!(file, "{{\"session\":\"x\",\"timest").unwrap();
+
+        let read = Journal::read(&path).unwrap();
+        assert_eq!(read.records.len(), 2);
+        assert_eq!(read.records[1].path, Path::new("/p/b.jpg"));
+        assert_eq!(read.skipped, 2);
+    }
+}
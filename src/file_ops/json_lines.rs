@@ -0,0 +1,46 @@
+//! Reading the JSON Lines files DejaVu appends to
+//!
+//! A crash while appending can leave a truncated last line, and a file edited
+//! by hand can contain anything. Such lines are skipped and counted, so that
+//! one bad line does not make the rest of the file unreadable.
+
+use crate::error::Result;
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// Records read from a JSON Lines file
+#[derive(Debug)]
+pub struct JsonLines<T> {
+    pub records: Vec<T>,
+    /// Non-empty lines that could not be parsed
+    pub skipped: usize,
+}
+
+impl<T> Default for JsonLines<T> {
+    fn default() -> Self {
+        Self {
+            records: Vec::new(),
+            skipped: 0,
+        }
+    }
+}
+
+/// Every record in `path`, in file order; a missing file has none
+pub fn read<T: DeserializeOwned>(path: &Path) -> Result<JsonLines<T>> {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(JsonLines::default()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut lines = JsonLines::default();
+    for line in String::from_utf8_lossy(&data).lines().filter(|line| !line.trim().is_empty()) {
+        match serde_json::from_str(line) {
+            Ok(record) => lines.records.push(record),
+            Err(_) => lines.skipped += 1,
+        }
+    }
+    Ok(lines)
+}
@@ -0,0 +1,366 @@
+//! Replace duplicates with links to the kept original
+//!
+//! The duplicate's path keeps existing, so other tools that reference it
+//! continue to work, but its content is no longer stored twice. Hard links
+//! make both paths the same file; reflinks keep independent files that share
+//! their data extents until one of them is modified; symlinks point at the
+//! original and work across filesystems.
+
+use crate::error::{DejaVuError, Result};
+use crate::file_ops::verifier::DuplicateVerifier;
+use crate::models::{DuplicateGroup, FileInfo};
+use std::path::{Component, Path, PathBuf};
+
+/// How a symlink refers to the original
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SymlinkTarget {
+    /// Path relative to the symlink's directory; survives moving the whole tree
+    #[default]
+    Relative,
+    /// Canonical absolute path of the original
+    Absolute,
+}
+
+/// How a duplicate is replaced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkKind {
+    /// Hard link to the original's inode
+    Hard,
+    /// Copy-on-write clone sharing the original's extents
+    Reflink,
+    /// Symbolic link to the original
+    Symlink(SymlinkTarget),
+}
+
+impl LinkKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LinkKind::Hard => "硬链接",
+            LinkKind::Reflink => "reflink",
+            LinkKind::Symlink(_) => "符号链接",
+        }
+    }
+}
+
+pub struct FileLinker;
+
+impl FileLinker {
+    /// Replace `group.files[index]` with a link of the given kind; returns the
+    /// number of bytes reclaimed
+    pub fn link_from_group(group: &DuplicateGroup, index: usize, kind: LinkKind) -> Result<u64> {
+        match kind {
+            LinkKind::Hard => Self::hard_link_from_group(group, index),
+            LinkKind::Reflink => Self::reflink_from_group(group, index),
+            LinkKind::Symlink(target) => Self::symlink_from_group(group, index, target),
+        }
+    }
+
+    /// Run the checks `link_from_group` would, without replacing anything;
+    /// returns the number of bytes that would be reclaimed
+    pub fn check_from_group(group: &DuplicateGroup, index: usize, kind: LinkKind) -> Result<u64> {
+        let size = group.files.get(index).map(|f| f.size).unwrap_or(0);
+        match kind {
+            LinkKind::Hard => {
+                let (duplicate, original) = Self::verified_pair(group, index)?;
+                check_hard_link(duplicate, original)?;
+            }
+            LinkKind::Reflink => {
+                let (duplicate, _) = Self::verified_pair(group, index)?;
+                if cfg!(not(target_os = "linux")) {
+                    return Err(link_error(duplicate, "reflinks are only supported on Linux"));
+                }
+            }
+            LinkKind::Symlink(_) => {
+                Self::verified_pair(group, index)?;
+            }
+        }
+        Ok(size)
+    }
+
+    /// Replace `group.files[index]` with a hard link to the file being kept
+    ///
+    /// Only exact groups qualify: the duplicate must still be byte-identical
+    /// to the original, since the link makes both paths share one inode.
+    /// Returns the number of bytes reclaimed.
+    pub fn hard_link_from_group(group: &DuplicateGroup, index: usize) -> Result<u64> {
+        let (duplicate, original) = Self::verified_pair(group, index)?;
+        let size = group.files[index].size;
+        Self::hard_link(duplicate, original)?;
+        Ok(size)
+    }
+
+    /// Make `group.files[index]` share its data extents with the file being kept
+    ///
+    /// Like hard links this requires an exact group. Returns the number of
+    /// bytes now shared with the original.
+    pub fn reflink_from_group(group: &DuplicateGroup, index: usize) -> Result<u64> {
+        let (duplicate, original) = Self::verified_pair(group, index)?;
+        Self::reflink(duplicate, original)
+    }
+
+    /// Replace `group.files[index]` with a symlink to the file being kept
+    ///
+    /// Like hard links this requires an exact group: reading the path must
+    /// still give the duplicate's content once it points at the original.
+    /// Returns the number of bytes reclaimed.
+    pub fn symlink_from_group(group: &DuplicateGroup, index: usize, target: SymlinkTarget) -> Result<u64> {
+        let (duplicate, original) = Self::verified_pair(group, index)?;
+        let size = group.files[index].size;
+        Self::symlink(duplicate, original, target)?;
+        Ok(size)
+    }
+
+    /// Atomically replace `duplicate` with a symlink to `original`
+    pub fn symlink(duplicate: &Path, original: &Path, target: SymlinkTarget) -> Result<()> {
+        let original = original.canonicalize().map_err(|e| link_error(duplicate, e))?;
+        let link_target = match target {
+            SymlinkTarget::Absolute => original,
+            SymlinkTarget::Relative => {
+                let dir = duplicate.parent().unwrap_or(Path::new("."));
+                let dir = dir.canonicalize().map_err(|e| link_error(duplicate, e))?;
+                relative_path(&dir, &original)
+            }
+        };
+
+        let temp = temp_path(duplicate)?;
+        create_symlink(&link_target, &temp).map_err(|e| link_error(duplicate, e))?;
+        if let Err(e) = std::fs::rename(&temp, duplicate) {
+            let _ = std::fs::remove_file(&temp);
+            return Err(link_error(duplicate, e));
+        }
+        Ok(())
+    }
+
+    /// Share `original`'s extents with `duplicate` (Linux copy-on-write filesystems)
+    #[cfg(target_os = "linux")]
+    pub fn reflink(duplicate: &Path, original: &Path) -> Result<u64> {
+        crate::file_ops::reflink::reflink(original, duplicate).map_err(|e| link_error(duplicate, e))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn reflink(duplicate: &Path, _original: &Path) -> Result<u64> {
+        Err(link_error(duplicate, "reflinks are only supported on Linux"))
+    }
+
+    /// Atomically replace `duplicate` with a hard link to `original`
+    ///
+    /// The link is created under a temporary name next to `duplicate` and then
+    /// renamed over it, so `duplicate` is never missing. The link shares the
+    /// original's inode and therefore its permissions and ownership; the
+    /// original itself is not modified.
+    pub fn hard_link(duplicate: &Path, original: &Path) -> Result<()> {
+        check_hard_link(duplicate, original)?;
+
+        let temp = temp_path(duplicate)?;
+        std::fs::hard_link(original, &temp).map_err(|e| link_error(duplicate, e))?;
+        if let Err(e) = std::fs::rename(&temp, duplicate) {
+            let _ = std::fs::remove_file(&temp);
+            return Err(link_error(duplicate, e));
+        }
+        Ok(())
+    }
+
+    /// The duplicate at `index` and the byte-identical file it is verified against
+    fn verified_pair(group: &DuplicateGroup, index: usize) -> Result<(&Path, &Path)> {
+        let (file, original) = Self::checked_pair(group, index)?;
+        if !group.is_exact_duplicate() {
+            return Err(link_error(&file.path, "only files with identical content can be linked"));
+        }
+
+        DuplicateVerifier::verify(file, original, true)?;
+        Ok((&file.path, &original.path))
+    }
+
+    /// The duplicate at `index` and the file kept in its place
+    fn checked_pair(group: &DuplicateGroup, index: usize) -> Result<(&FileInfo, &FileInfo)> {
+        let Some(file) = group.files.get(index) else {
+            return Err(DejaVuError::FileOperationFailed(format!(
+                "Group #{} has no file #{}",
+                group.group_id + 1,
+                index + 1
+            )));
+        };
+        let Some(original) = group.reference_for(index) else {
+            return Err(DejaVuError::VerificationFailed(
+                file.path.display().to_string(),
+                "no other file in the group is kept".to_string(),
+            ));
+        };
+        Ok((file, original))
+    }
+}
+
+/// Whether `duplicate` can be replaced by a hard link to `original`
+fn check_hard_link(duplicate: &Path, original: &Path) -> Result<()> {
+    if !same_filesystem(duplicate, original)? {
+        return Err(link_error(duplicate, "hard links require both files on the same filesystem"));
+    }
+    if same_inode(duplicate, original)? {
+        return Err(link_error(duplicate, "already a hard link to the original"));
+    }
+    Ok(())
+}
+
+/// Unused name in the same directory as `path`, e.g. `.photo.jpg.dejavu-123-0`
+fn temp_path(path: &Path) -> Result<PathBuf> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let name = path
+        .file_name()
+        .ok_or_else(|| link_error(path, "no file name"))?
+        .to_string_lossy();
+
+    (0..100)
+        .map(|n| dir.join(format!(".{}.dejavu-{}-{}", name, std::process::id(), n)))
+        .find(|candidate| std::fs::symlink_metadata(candidate).is_err())
+        .ok_or_else(|| link_error(path, "no free temporary name"))
+}
+
+/// Path to `to` as seen from directory `from`; both must be absolute
+fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from: Vec<_> = from.components().collect();
+    let to: Vec<_> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    std::iter::repeat_n(Component::ParentDir, from.len() - common)
+        .chain(to[common..].iter().copied())
+        .collect()
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn create_symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
+#[cfg(unix)]
+fn same_filesystem(a: &Path, b: &Path) -> Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+    Ok(metadata(a)?.dev() == metadata(b)?.dev())
+}
+
+/// Elsewhere the link call itself reports cross-device links
+#[cfg(not(unix))]
+fn same_filesystem(_a: &Path, _b: &Path) -> Result<bool> {
+    Ok(true)
+}
+
+#[cfg(unix)]
+fn same_inode(a: &Path, b: &Path) -> Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+    let (a, b) = (metadata(a)?, metadata(b)?);
+    Ok(a.dev() == b.dev() && a.ino() == b.ino())
+}
+
+#[cfg(not(unix))]
+fn same_inode(_a: &Path, _b: &Path) -> Result<bool> {
+    Ok(false)
+}
+
+fn metadata(path: &Path) -> Result<std::fs::Metadata> {
+    std::fs::metadata(path).map_err(|e| link_error(path, e))
+}
+
+fn link_error(path: &Path, reason: impl std::fmt::Display) -> DejaVuError {
+    DejaVuError::FileOperationFailed(format!("Failed to link {}: {}", path.display(), reason))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::hashing::ExactHashAlgorithm;
+    use crate::models::{FileInfo, GroupKind};
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+    use tempfile::TempDir;
+
+    /// An exact group whose recommended original is `a.jpg`
+    fn group(dir: &TempDir, duplicate_content: &[u8]) -> DuplicateGroup {
+        let a = dir.path().join("a.jpg");
+        let b = dir.path().join("copies").join("b.jpg");
+        std::fs::create_dir_all(b.parent().unwrap()).unwrap();
+        std::fs::write(&a, b"same bytes").unwrap();
+        std::fs::write(&b, duplicate_content).unwrap();
+        std::fs::set_permissions(&a, std::fs::Permissions::from_mode(0o640)).unwrap();
+
+        let group = DuplicateGroup::new(0, vec![FileInfo::scanned(&a), FileInfo::scanned(&b)])
+            .with_exact_hash(vec![1], ExactHashAlgorithm::Sha256);
+        assert_eq!(group.recommended_original, 0);
+        group
+    }
+
+    #[test]
+    fn test_hard_link_replaces_duplicate() {
+        let dir = TempDir::new().unwrap();
+        let group = group(&dir, b"same bytes");
+
+        FileLinker::hard_link_from_group(&group, 1).unwrap();
+
+        let original = std::fs::metadata(&group.files[0].path).unwrap();
+        let linked = std::fs::metadata(&group.files[1].path).unwrap();
+        assert_eq!(linked.ino(), original.ino());
+        assert_eq!(original.nlink(), 2);
+        assert_eq!(linked.permissions().mode() & 0o777, 0o640);
+        assert_eq!(std::fs::read_dir(group.files[1].path.parent().unwrap()).unwrap().count(), 1);
+
+        // Linking again is refused rather than silently repeated
+        assert!(FileLinker::hard_link(&group.files[1].path, &group.files[0].path).is_err());
+    }
+
+    #[test]
+    fn test_symlink_targets() {
+        let dir = TempDir::new().unwrap();
+        let group = group(&dir, b"same bytes");
+        let original = group.files[0].path.canonicalize().unwrap();
+
+        FileLinker::symlink_from_group(&group, 1, SymlinkTarget::Relative).unwrap();
+        let link = &group.files[1].path;
+        assert_eq!(std::fs::read_link(link).unwrap(), Path::new("../a.jpg"));
+        assert_eq!(std::fs::read(link).unwrap(), b"same bytes");
+
+        FileLinker::symlink(link, &group.files[0].path, SymlinkTarget::Absolute).unwrap();
+        assert_eq!(std::fs::read_link(link).unwrap(), original);
+    }
+
+    #[test]
+    fn test_symlink_requires_existing_original() {
+        let dir = TempDir::new().unwrap();
+        let group = group(&dir, b"same bytes");
+        std::fs::remove_file(&group.files[0].path).unwrap();
+
+        assert!(FileLinker::symlink_from_group(&group, 1, SymlinkTarget::Relative).is_err());
+        assert_eq!(std::fs::read(&group.files[1].path).unwrap(), b"same bytes");
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(relative_path(Path::new("/a/b/c"), Path::new("/a/d/e.jpg")), Path::new("../../d/e.jpg"));
+        assert_eq!(relative_path(Path::new("/a"), Path::new("/a/e.jpg")), Path::new("e.jpg"));
+    }
+
+    #[test]
+    fn test_changed_or_similar_files_are_not_linked() {
+        let dir = TempDir::new().unwrap();
+        let group = group(&dir, b"s4me bytes");
+        assert!(FileLinker::hard_link_from_group(&group, 1).is_err());
+        assert_eq!(std::fs::read(&group.files[1].path).unwrap(), b"s4me bytes");
+
+        let similar = group.with_kind(GroupKind::Similar);
+        assert!(FileLinker::hard_link_from_group(&similar, 1).is_err());
+    }
+
+    #[test]
+    fn test_similar_files_are_not_symlinked() {
+        let dir = TempDir::new().unwrap();
+        let similar = group(&dir, b"same bytes").with_kind(GroupKind::Similar);
+        let kind = LinkKind::Symlink(SymlinkTarget::Relative);
+
+        assert!(FileLinker::check_from_group(&similar, 1, kind).is_err());
+        assert!(FileLinker::link_from_group(&similar, 1, kind).is_err());
+        let duplicate = &similar.files[1].path;
+        assert!(!std::fs::symlink_metadata(duplicate).unwrap().file_type().is_symlink());
+        assert_eq!(std::fs::read(duplicate).unwrap(), b"same bytes");
+    }
+}
@@ -1,5 +1,21 @@
 pub mod opener;
 pub mod deleter;
+pub mod executor;
+pub mod journal;
+pub mod json_lines;
+pub mod linker;
+pub mod quarantine;
+#[cfg(target_os = "linux")]
+pub mod reflink;
+pub mod restore;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub mod trash;
+pub mod verifier;
 
 pub use opener::FileOpener;
 pub use deleter::FileDeleter;
+pub use executor::{ActionExecutor, FileAction};
+pub use journal::Journal;
+pub use linker::{FileLinker, LinkKind, SymlinkTarget};
+pub use quarantine::Quarantine;
+pub use restore::{TrashHistory, TrashedFile};
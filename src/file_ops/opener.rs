@@ -18,6 +18,7 @@ impl FileOpener {
 
     /// Show a file in the system's file manager
     #[cfg(target_os = "macos")]
+    #[allow(dead_code)]
     pub fn reveal(path: &Path) -> Result<()> {
         std::process::Command::new("open")
             .arg("-R")
@@ -34,6 +35,7 @@ impl FileOpener {
     }
 
     #[cfg(target_os = "linux")]
+    #[allow(dead_code)]
     pub fn reveal(path: &Path) -> Result<()> {
         std::process::Command::new("dbus-send")
             .args([
@@ -56,6 +58,7 @@ impl FileOpener {
     }
 
     #[cfg(target_os = "windows")]
+    #[allow(dead_code)]
     pub fn reveal(path: &Path) -> Result<()> {
         std::process::Command::new("explorer")
             .arg("/select,")
@@ -0,0 +1,262 @@
+//! Quarantine directory
+//!
+//! Instead of deleting duplicates, moves them below a quarantine directory
+//! that mirrors their original location, e.g. `/home/me/photos/a.jpg` ends up
+//! at `$QUARANTINE/home/me/photos/a.jpg`. Every move is appended to
+//! `$QUARANTINE/manifest.jsonl`, so a human can review and purge later.
+
+use crate::error::{DejaVuError, Result};
+use crate::file_ops::verifier::DuplicateVerifier;
+use crate::models::DuplicateGroup;
+use crate::utils::numbered_name;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Component, Path, PathBuf};
+
+pub const MANIFEST_FILE_NAME: &str = "manifest.jsonl";
+
+/// One manifest entry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuarantinedFile {
+    /// Where the duplicate lived
+    pub original_path: PathBuf,
+    /// Where it was moved to
+    pub quarantined_path: PathBuf,
+    /// The file that was kept instead
+    pub kept_path: PathBuf,
+    pub size: u64,
+    /// Local time of the move
+    pub moved_at: String,
+}
+
+pub struct Quarantine {
+    root: PathBuf,
+}
+
+impl Quarantine {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    #[cfg(test)]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn manifest_path(&self) -> PathBuf {
+        self.root.join(MANIFEST_FILE_NAME)
+    }
+
+    /// Move `group.files[index]` into quarantine after verifying it against
+    /// the file being kept
+    ///
+    /// The move is not in the manifest yet: pass the returned entry to
+    /// `record`, whose failure no longer undoes anything.
+    pub fn move_from_group(&self, group: &DuplicateGroup, index: usize) -> Result<QuarantinedFile> {
+        let Some(file) = group.files.get(index) else {
+            return Err(DejaVuError::FileOperationFailed(format!(
+                "Group #{} has no file #{}",
+                group.group_id + 1,
+                index + 1
+            )));
+        };
+        let Some(original) = group.reference_for(index) else {
+            return Err(DejaVuError::VerificationFailed(
+                file.path.display().to_string(),
+                "no other file in the group is kept".to_string(),
+            ));
+        };
+        DuplicateVerifier::verify(file, original, group.is_exact_duplicate())?;
+
+        let quarantined_path = self.move_file(&file.path)?;
+        let entry = QuarantinedFile {
+            original_path: std::path::absolute(&file.path)?,
+            quarantined_path,
+            kept_path: std::path::absolute(&original.path)?,
+            size: file.size,
+            moved_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        };
+        Ok(entry)
+    }
+
+    /// Move `path` to its mirrored location, numbering the name on collisions
+    pub fn move_file(&self, path: &Path) -> Result<PathBuf> {
+        let source = std::path::absolute(path).map_err(|e| quarantine_error(path, e))?;
+        let name = source
+            .file_name()
+            .ok_or_else(|| quarantine_error(path, "no file name"))?;
+        let dir = self.mirrored_dir(&source);
+        fs::create_dir_all(&dir).map_err(|e| quarantine_error(path, e))?;
+
+        for n in 1.. {
+            let target = dir.join(numbered_name(name, n));
+            match place(&source, &target) {
+                Ok(()) => return Ok(target),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(quarantine_error(path, e)),
+            }
+        }
+        unreachable!("unbounded name search")
+    }
+
+    /// Directory below the quarantine root mirroring the parent of the
+    /// absolute path `source`
+    pub fn mirrored_dir(&self, source: &Path) -> PathBuf {
+        let relative: PathBuf = source
+            .parent()
+            .unwrap_or(Path::new(""))
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(part) => Some(part),
+                _ => None,
+            })
+            .collect();
+        self.root.join(relative)
+    }
+
+    /// Append a completed move to the manifest
+    pub fn record(&self, entry: &QuarantinedFile) -> Result<()> {
+        let line = serde_json::to_string(entry)
+            .map_err(|e| DejaVuError::FileOperationFailed(format!("Failed to write quarantine manifest: {}", e)))?;
+        let mut manifest = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.manifest_path())?;
+        writeln!(manifest, "{}", line)?;
+        Ok(())
+    }
+}
+
+/// Move `source` to `target`, failing with `AlreadyExists` instead of overwriting
+fn place(source: &Path, target: &Path) -> io::Result<()> {
+    match fs::hard_link(source, target) {
+        Ok(()) => fs::remove_file(source),
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => Err(e),
+        Err(e) if e.kind() == ErrorKind::CrossesDevices => copy_then_remove(source, target),
+        // Filesystem without hard links
+        Err(_) => {
+            if fs::symlink_metadata(target).is_ok() {
+                return Err(ErrorKind::AlreadyExists.into());
+            }
+            fs::rename(source, target)
+        }
+    }
+}
+
+/// Cross-device move: the source is only removed once the copy is on disk
+fn copy_then_remove(source: &Path, target: &Path) -> io::Result<()> {
+    let metadata = fs::metadata(source)?;
+    let mut output = OpenOptions::new().write(true).create_new(true).open(target)?;
+
+    let copied = io::copy(&mut File::open(source)?, &mut output)
+        .and_then(|copied| {
+            if copied != metadata.len() {
+                return Err(io::Error::other(format!(
+                    "copied {} of {} bytes",
+                    copied,
+                    metadata.len()
+                )));
+            }
+            output.set_permissions(metadata.permissions())?;
+            output.set_modified(metadata.modified()?)?;
+            output.sync_all()
+        })
+        .and_then(|_| sync_dir(target));
+
+    if let Err(e) = copied {
+        let _ = fs::remove_file(target);
+        return Err(e);
+    }
+    fs::remove_file(source)
+}
+
+/// Persist the directory entry of a newly created file
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(dir) => File::open(dir)?.sync_all(),
+        None => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+fn quarantine_error(path: &Path, reason: impl std::fmt::Display) -> DejaVuError {
+    DejaVuError::FileOperationFailed(format!("Failed to quarantine {}: {}", path.display(), reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::FileInfo;
+    use tempfile::TempDir;
+
+    fn write(path: &Path, data: &[u8]) -> PathBuf {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, data).unwrap();
+        path.to_path_buf()
+    }
+
+    #[test]
+    fn test_move_mirrors_structure_and_writes_manifest() {
+        let dir = TempDir::new().unwrap();
+        let quarantine = Quarantine::new(dir.path().join("quarantine"));
+        let kept = write(&dir.path().join("photos/a.jpg"), b"same bytes");
+        let duplicate = write(&dir.path().join("photos/copies/b.jpg"), b"same bytes");
+        let group = DuplicateGroup::new(0, vec![FileInfo::scanned(&kept), FileInfo::scanned(&duplicate)]);
+
+        let entry = quarantine.move_from_group(&group, 1).unwrap();
+        quarantine.record(&entry).unwrap();
+
+        let relative = duplicate.strip_prefix("/").unwrap();
+        assert_eq!(entry.quarantined_path, quarantine.root().join(relative));
+        assert_eq!(fs::read(&entry.quarantined_path).unwrap(), b"same bytes");
+        assert!(!duplicate.exists());
+        assert!(kept.exists());
+
+        let manifest = fs::read_to_string(quarantine.manifest_path()).unwrap();
+        let recorded: QuarantinedFile = serde_json::from_str(manifest.lines().next().unwrap()).unwrap();
+        assert_eq!(recorded, entry);
+        assert_eq!(recorded.kept_path, kept);
+    }
+
+    #[test]
+    fn test_name_collisions_are_numbered() {
+        let dir = TempDir::new().unwrap();
+        let quarantine = Quarantine::new(dir.path().join("quarantine"));
+        let file = dir.path().join("b.jpg");
+
+        write(&file, b"first");
+        let first = quarantine.move_file(&file).unwrap();
+        write(&file, b"second");
+        let second = quarantine.move_file(&file).unwrap();
+
+        assert_eq!(second.file_name().unwrap(), "b.2.jpg");
+        assert_eq!(fs::read(first).unwrap(), b"first");
+        assert_eq!(fs::read(second).unwrap(), b"second");
+    }
+
+    #[test]
+    fn test_copy_then_remove_keeps_metadata() {
+        let dir = TempDir::new().unwrap();
+        let source = write(&dir.path().join("a.jpg"), b"pixels");
+        let target = dir.path().join("moved.jpg");
+        let modified = fs::metadata(&source).unwrap().modified().unwrap();
+
+        copy_then_remove(&source, &target).unwrap();
+
+        assert!(!source.exists());
+        assert_eq!(fs::read(&target).unwrap(), b"pixels");
+        assert_eq!(fs::metadata(&target).unwrap().modified().unwrap(), modified);
+
+        // Never overwrites an existing file
+        write(&source, b"other");
+        assert_eq!(copy_then_remove(&source, &target).unwrap_err().kind(), ErrorKind::AlreadyExists);
+        assert!(source.exists());
+    }
+}
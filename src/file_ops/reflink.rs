@@ -0,0 +1,294 @@
+//! Copy-on-write extent sharing on Linux (btrfs, XFS, bcachefs, ...)
+//!
+//! `FIDEDUPERANGE` asks the kernel to share the original's extents with the
+//! duplicate. The kernel locks and compares both ranges itself and only shares
+//! them while they are identical, and the duplicate keeps its inode,
+//! permissions and timestamps. Filesystems that can clone but not dedupe get a
+//! `FICLONE` into a temporary file that is renamed over the duplicate; it takes
+//! over the duplicate's owner, permissions, extended attributes (ACLs, SELinux
+//! labels) and modification time, or the duplicate is left alone.
+
+use std::ffi::CString;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+/// Largest range passed to one `FIDEDUPERANGE` call (btrfs caps requests at 16 MiB)
+const DEDUPE_CHUNK: u64 = 16 * 1024 * 1024;
+
+const FILE_DEDUPE_RANGE_SAME: i32 = 0;
+const FILE_DEDUPE_RANGE_DIFFERS: i32 = 1;
+
+/// `struct file_dedupe_range` from linux/fs.h, without the trailing array
+#[repr(C)]
+struct FileDedupeRange {
+    src_offset: u64,
+    src_length: u64,
+    dest_count: u16,
+    reserved1: u16,
+    reserved2: u32,
+}
+
+/// `struct file_dedupe_range_info` from linux/fs.h
+#[repr(C)]
+struct FileDedupeRangeInfo {
+    dest_fd: i64,
+    dest_offset: u64,
+    bytes_deduped: u64,
+    status: i32,
+    reserved: u32,
+}
+
+/// A dedupe request with exactly one destination
+#[repr(C)]
+struct DedupeRequest {
+    range: FileDedupeRange,
+    info: [FileDedupeRangeInfo; 1],
+}
+
+const FIDEDUPERANGE: libc::Ioctl = libc::_IOWR::<FileDedupeRange>(0x94, 54);
+
+/// Why a reflink could not be made
+#[derive(Debug)]
+pub enum ReflinkError {
+    /// The filesystem cannot share extents between these files
+    Unsupported(io::Error),
+    /// The kernel found differing bytes
+    ContentDiffers,
+    Io(io::Error),
+}
+
+impl std::fmt::Display for ReflinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReflinkError::Unsupported(e) => write!(
+                f,
+                "the filesystem does not support reflinks ({}); use btrfs, XFS with reflink=1 or another copy-on-write filesystem",
+                e
+            ),
+            ReflinkError::ContentDiffers => write!(f, "content no longer matches the kept original"),
+            ReflinkError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<io::Error> for ReflinkError {
+    fn from(e: io::Error) -> Self {
+        ReflinkError::Io(e)
+    }
+}
+
+/// Make `duplicate` share `original`'s extents; returns the number of bytes shared
+pub fn reflink(original: &Path, duplicate: &Path) -> Result<u64, ReflinkError> {
+    let source = File::open(original)?;
+    let target = OpenOptions::new().read(true).write(true).open(duplicate)?;
+    let len = source.metadata()?.len();
+    if target.metadata()?.len() != len {
+        return Err(ReflinkError::ContentDiffers);
+    }
+
+    match dedupe(&source, &target, len) {
+        Err(ReflinkError::Unsupported(_)) => {
+            drop(target);
+            clone_over(&source, duplicate).map(|_| len)
+        }
+        result => result,
+    }
+}
+
+/// Share all of `len` bytes via `FIDEDUPERANGE`, chunk by chunk
+fn dedupe(source: &File, target: &File, len: u64) -> Result<u64, ReflinkError> {
+    let mut offset = 0;
+    let mut shared = 0;
+
+    while offset < len {
+        let mut request = DedupeRequest {
+            range: FileDedupeRange {
+                src_offset: offset,
+                src_length: DEDUPE_CHUNK.min(len - offset),
+                dest_count: 1,
+                reserved1: 0,
+                reserved2: 0,
+            },
+            info: [FileDedupeRangeInfo {
+                dest_fd: target.as_raw_fd() as i64,
+                dest_offset: offset,
+                bytes_deduped: 0,
+                status: 0,
+                reserved: 0,
+            }],
+        };
+
+        // SAFETY: `request` is a valid file_dedupe_range followed by `dest_count`
+        // info entries and outlives the call
+        let ret = unsafe { libc::ioctl(source.as_raw_fd(), FIDEDUPERANGE, &mut request) };
+        if ret < 0 {
+            return Err(classify(io::Error::last_os_error()));
+        }
+
+        let info = &request.info[0];
+        match info.status {
+            FILE_DEDUPE_RANGE_SAME => {}
+            FILE_DEDUPE_RANGE_DIFFERS => return Err(ReflinkError::ContentDiffers),
+            errno => return Err(classify(io::Error::from_raw_os_error(-errno))),
+        }
+        if info.bytes_deduped == 0 {
+            break;
+        }
+
+        offset += info.bytes_deduped;
+        shared += info.bytes_deduped;
+    }
+
+    Ok(shared)
+}
+
+/// Replace `duplicate` with a `FICLONE` of `source`, keeping the duplicate's
+/// owner, permissions, extended attributes and modification time
+///
+/// If any of them cannot be carried over, e.g. because only root may give the
+/// clone another user's ownership, the duplicate is not replaced.
+fn clone_over(source: &File, duplicate: &Path) -> Result<(), ReflinkError> {
+    let original = File::open(duplicate)?;
+    let metadata = original.metadata()?;
+    let dir = duplicate.parent().unwrap_or(Path::new("."));
+    let temp = tempfile::Builder::new().prefix(".dejavu-reflink-").tempfile_in(dir)?;
+
+    // SAFETY: both descriptors are open for the duration of the call
+    let ret = unsafe { libc::ioctl(temp.as_file().as_raw_fd(), libc::FICLONE, source.as_raw_fd()) };
+    if ret < 0 {
+        return Err(classify(io::Error::last_os_error()));
+    }
+
+    // Ownership first: changing it clears the setuid and setgid bits
+    std::os::unix::fs::fchown(temp.as_file(), Some(metadata.uid()), Some(metadata.gid()))?;
+    temp.as_file().set_permissions(metadata.permissions())?;
+    copy_xattrs(&original, temp.as_file())?;
+    temp.as_file().set_modified(metadata.modified()?)?;
+    temp.persist(duplicate).map_err(|e| ReflinkError::Io(e.error))?;
+    Ok(())
+}
+
+/// Copy every extended attribute `from` lists (ACLs, SELinux labels, `user.*`)
+/// to `to`; a filesystem without extended attributes has none to copy
+fn copy_xattrs(from: &File, to: &File) -> io::Result<()> {
+    // SAFETY: `buf` is valid for writes of `len` bytes, or null with a length of 0
+    let names = match read_sized(|buf, len| unsafe { libc::flistxattr(from.as_raw_fd(), buf, len) }) {
+        Err(e) if e.raw_os_error() == Some(libc::ENOTSUP) => return Ok(()),
+        names => names?,
+    };
+
+    for name in names.split(|&b| b == 0).filter(|name| !name.is_empty()) {
+        let name = CString::new(name).map_err(io::Error::other)?;
+        // SAFETY: as above; `name` is a nul-terminated attribute name
+        let value = read_sized(|buf, len| unsafe {
+            libc::fgetxattr(from.as_raw_fd(), name.as_ptr(), buf.cast(), len)
+        })?;
+        // SAFETY: `value` is valid for reads of its length
+        let ret = unsafe {
+            libc::fsetxattr(to.as_raw_fd(), name.as_ptr(), value.as_ptr().cast(), value.len(), 0)
+        };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Run a `*xattr` call once for the size and again for the data, retrying
+/// when the data grew in between
+fn read_sized(mut call: impl FnMut(*mut libc::c_char, usize) -> libc::ssize_t) -> io::Result<Vec<u8>> {
+    loop {
+        let len = call(std::ptr::null_mut(), 0);
+        if len < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut buf = vec![0u8; len as usize];
+        let read = call(buf.as_mut_ptr().cast(), buf.len());
+        if read >= 0 {
+            buf.truncate(read as usize);
+            return Ok(buf);
+        }
+        let e = io::Error::last_os_error();
+        if e.raw_os_error() != Some(libc::ERANGE) {
+            return Err(e);
+        }
+    }
+}
+
+/// Errors that mean "not on this filesystem" rather than a failure
+fn classify(e: io::Error) -> ReflinkError {
+    match e.raw_os_error() {
+        Some(libc::EOPNOTSUPP | libc::ENOTTY | libc::EINVAL | libc::EXDEV | libc::ENOSYS) => {
+            ReflinkError::Unsupported(e)
+        }
+        _ => ReflinkError::Io(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_reflink_or_clear_unsupported_error() {
+        let dir = TempDir::new().unwrap();
+        let original = dir.path().join("a.jpg");
+        let duplicate = dir.path().join("b.jpg");
+        std::fs::write(&original, vec![7u8; 64 * 1024]).unwrap();
+        std::fs::write(&duplicate, vec![7u8; 64 * 1024]).unwrap();
+
+        // tmpfs and ext4 cannot share extents; btrfs and XFS can
+        match reflink(&original, &duplicate) {
+            Ok(shared) => {
+                assert_eq!(shared, 64 * 1024);
+                assert_eq!(std::fs::read(&duplicate).unwrap(), vec![7u8; 64 * 1024]);
+            }
+            Err(ReflinkError::Unsupported(_)) => {
+                assert_eq!(std::fs::read(&duplicate).unwrap(), vec![7u8; 64 * 1024]);
+                assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+            }
+            Err(e) => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_copy_xattrs() {
+        let dir = TempDir::new().unwrap();
+        let from = File::create(dir.path().join("a.jpg")).unwrap();
+        let to = File::create(dir.path().join("b.jpg")).unwrap();
+        let name = CString::new("user.dejavu.test").unwrap();
+        let value = b"kept";
+
+        // SAFETY: `name` and `value` outlive the call
+        let ret = unsafe {
+            libc::fsetxattr(from.as_raw_fd(), name.as_ptr(), value.as_ptr().cast(), value.len(), 0)
+        };
+        if ret < 0 {
+            // The filesystem has no user attributes to keep
+            return;
+        }
+
+        copy_xattrs(&from, &to).unwrap();
+        // SAFETY: as in `copy_xattrs`
+        let copied = read_sized(|buf, len| unsafe {
+            libc::fgetxattr(to.as_raw_fd(), name.as_ptr(), buf.cast(), len)
+        })
+        .unwrap();
+        assert_eq!(copied, value);
+    }
+
+    #[test]
+    fn test_different_sizes_are_refused() {
+        let dir = TempDir::new().unwrap();
+        let original = dir.path().join("a.jpg");
+        let duplicate = dir.path().join("b.jpg");
+        std::fs::write(&original, b"same bytes").unwrap();
+        std::fs::write(&duplicate, b"same bytes, longer").unwrap();
+
+        assert!(matches!(reflink(&original, &duplicate), Err(ReflinkError::ContentDiffers)));
+    }
+}
@@ -0,0 +1,293 @@
+//! Undo for trash deletions
+//!
+//! Every batch of files DejaVu moves to the trash is appended to a history
+//! file, one JSON object per line. Undo takes the most recent batch and moves
+//! its files back to their original paths; files that cannot be restored yet
+//! (e.g. because another file now occupies the original path) stay in the
+//! history so the undo can be retried. Lines that cannot be parsed are
+//! skipped, and dropped once an undo rewrites the history.
+
+use super::json_lines::{self, JsonLines};
+use crate::error::{DejaVuError, Result};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+const HISTORY_FILE_NAME: &str = "trash-history.jsonl";
+
+/// A file that was moved to the trash
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrashedFile {
+    /// Where the file lived before it was trashed
+    pub original_path: PathBuf,
+    /// Current location inside the trash's `files` directory
+    pub trashed_path: PathBuf,
+    /// The `.trashinfo` record describing it
+    pub info_path: PathBuf,
+}
+
+/// Files trashed by one delete command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashBatch {
+    /// Local time the batch was trashed
+    pub created_at: String,
+    pub files: Vec<TrashedFile>,
+}
+
+/// Result of restoring one batch
+#[derive(Debug, Default)]
+pub struct RestoreReport {
+    pub restored: Vec<TrashedFile>,
+    /// Original path is occupied by another file
+    pub conflicts: Vec<TrashedFile>,
+    /// No longer in the trash, e.g. because the trash was emptied
+    pub missing: Vec<TrashedFile>,
+    pub failed: Vec<(TrashedFile, String)>,
+    /// History lines that could not be parsed and were dropped
+    pub skipped_lines: usize,
+}
+
+impl RestoreReport {
+    /// Files that may still be restored by a later attempt
+    fn remaining(&self) -> Vec<TrashedFile> {
+        self.conflicts
+            .iter()
+            .cloned()
+            .chain(self.failed.iter().map(|(f, _)| f.clone()))
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.conflicts.is_empty() && self.missing.is_empty() && self.failed.is_empty()
+    }
+}
+
+enum RestoreOutcome {
+    Restored,
+    Conflict,
+    Missing,
+}
+
+/// Trash batches in the order they were created
+pub struct TrashHistory {
+    path: PathBuf,
+}
+
+impl TrashHistory {
+    /// Default location: `$XDG_DATA_HOME/dejavu/trash-history.jsonl`
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("dejavu").join(HISTORY_FILE_NAME))
+    }
+
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Append a batch; empty batches are not recorded
+    ///
+    /// Files whose paths cannot be written as JSON (not valid UTF-8) are left
+    /// out rather than losing the whole batch; returns how many were.
+    pub fn record(&self, mut files: Vec<TrashedFile>) -> Result<usize> {
+        let count = files.len();
+        files.retain(|file| serde_json::to_string(file).is_ok());
+        let left_out = count - files.len();
+        if files.is_empty() {
+            return Ok(left_out);
+        }
+
+        let batch = TrashBatch {
+            created_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            files,
+        };
+        let line = serde_json::to_string(&batch).map_err(history_error)?;
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", line)?;
+        Ok(left_out)
+    }
+
+    /// All recorded batches, oldest first, skipping lines that are not
+    /// valid batches
+    pub fn batches(&self) -> Result<JsonLines<TrashBatch>> {
+        json_lines::read(&self.path)
+    }
+
+    /// Restore the most recent batch, or return `None` if there is nothing to undo
+    pub fn undo_last(&self) -> Result<Option<(TrashBatch, RestoreReport)>> {
+        let JsonLines { records: mut batches, skipped } = self.batches()?;
+        let Some(batch) = batches.pop() else {
+            return Ok(None);
+        };
+
+        let mut report = RestoreReport {
+            skipped_lines: skipped,
+            ..Default::default()
+        };
+        for file in &batch.files {
+            match restore_file(file) {
+                Ok(RestoreOutcome::Restored) => report.restored.push(file.clone()),
+                Ok(RestoreOutcome::Conflict) => report.conflicts.push(file.clone()),
+                Ok(RestoreOutcome::Missing) => report.missing.push(file.clone()),
+                Err(e) => report.failed.push((file.clone(), e.to_string())),
+            }
+        }
+
+        let remaining = report.remaining();
+        if !remaining.is_empty() {
+            batches.push(TrashBatch {
+                created_at: batch.created_at.clone(),
+                files: remaining,
+            });
+        }
+        self.write_batches(&batches)?;
+
+        Ok(Some((batch, report)))
+    }
+
+    /// Rewrite the history atomically
+    fn write_batches(&self, batches: &[TrashBatch]) -> Result<()> {
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(dir)?;
+
+        let mut temp = tempfile::NamedTempFile::new_in(dir)?;
+        for batch in batches {
+            writeln!(temp, "{}", serde_json::to_string(batch).map_err(history_error)?)?;
+        }
+        temp.persist(&self.path)
+            .map_err(|e| DejaVuError::FileOperationFailed(format!("Failed to write {}: {}", self.path.display(), e)))?;
+        Ok(())
+    }
+}
+
+/// Move one file back to its original path without overwriting anything
+fn restore_file(file: &TrashedFile) -> std::io::Result<RestoreOutcome> {
+    if fs::symlink_metadata(&file.original_path).is_ok() {
+        return Ok(RestoreOutcome::Conflict);
+    }
+    if fs::symlink_metadata(&file.trashed_path).is_err() {
+        return Ok(RestoreOutcome::Missing);
+    }
+
+    if let Some(parent) = file.original_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // Linking fails if the target appeared in the meantime, unlike rename
+    match fs::hard_link(&file.trashed_path, &file.original_path) {
+        Ok(()) => fs::remove_file(&file.trashed_path)?,
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => return Ok(RestoreOutcome::Conflict),
+        // Filesystem without hard links
+        Err(_) => fs::rename(&file.trashed_path, &file.original_path)?,
+    }
+
+    let _ = fs::remove_file(&file.info_path);
+    Ok(RestoreOutcome::Restored)
+}
+
+fn history_error(e: serde_json::Error) -> DejaVuError {
+    DejaVuError::FileOperationFailed(format!("Invalid trash history: {}", e))
+}
+
+#[cfg(all(test, unix, not(target_os = "macos")))]
+mod tests {
+    use super::*;
+    use crate::file_ops::trash::XdgTrash;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, XdgTrash, TrashHistory) {
+        let home = TempDir::new().unwrap();
+        let trash = XdgTrash::new(home.path().join(".local/share/Trash"));
+        let history = TrashHistory::new(home.path().join(".local/share/dejavu").join(HISTORY_FILE_NAME));
+        (home, trash, history)
+    }
+
+    fn write(path: &Path, data: &[u8]) -> PathBuf {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, data).unwrap();
+        path.to_path_buf()
+    }
+
+    #[test]
+    fn test_undo_restores_last_batch_only() {
+        let (home, trash, history) = setup();
+        let first = write(&home.path().join("photos/a.jpg"), b"a");
+        let second = write(&home.path().join("photos/b.jpg"), b"b");
+
+        history.record(vec![trash.trash(&first).unwrap()]).unwrap();
+        let trashed = trash.trash(&second).unwrap();
+        history.record(vec![trashed.clone()]).unwrap();
+
+        let (_, report) = history.undo_last().unwrap().unwrap();
+        assert!(report.is_complete());
+        assert_eq!(fs::read(&second).unwrap(), b"b");
+        assert!(!trashed.trashed_path.exists());
+        assert!(!trashed.info_path.exists());
+        assert!(!first.exists());
+
+        assert_eq!(history.batches().unwrap().records.len(), 1);
+        history.undo_last().unwrap().unwrap();
+        assert_eq!(fs::read(&first).unwrap(), b"a");
+        assert!(history.undo_last().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_conflicts_are_kept_for_retry() {
+        let (home, trash, history) = setup();
+        let file = write(&home.path().join("a.jpg"), b"original");
+        history.record(vec![trash.trash(&file).unwrap()]).unwrap();
+        write(&file, b"newcomer");
+
+        let (_, report) = history.undo_last().unwrap().unwrap();
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(fs::read(&file).unwrap(), b"newcomer");
+        assert_eq!(history.batches().unwrap().records.len(), 1);
+
+        fs::remove_file(&file).unwrap();
+        let (_, report) = history.undo_last().unwrap().unwrap();
+        assert!(report.is_complete());
+        assert_eq!(fs::read(&file).unwrap(), b"original");
+    }
+
+    #[test]
+    fn test_corrupt_line_does_not_block_undo() {
+        let (home, trash, history) = setup();
+        let first = write(&home.path().join("a.jpg"), b"a");
+        let second = write(&home.path().join("b.jpg"), b"b");
+        history.record(vec![trash.trash(&first).unwrap()]).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&history.path).unwrap();
+        writeln!(file, "{{\"created_at\":").unwrap();
+        history.record(vec![trash.trash(&second).unwrap()]).unwrap();
+
+        let (_, report) = history.undo_last().unwrap().unwrap();
+        assert!(report.is_complete());
+        assert_eq!(report.skipped_lines, 1);
+        assert_eq!(fs::read(&second).unwrap(), b"b");
+
+        // The rewrite dropped the bad line
+        let (_, report) = history.undo_last().unwrap().unwrap();
+        assert_eq!(report.skipped_lines, 0);
+        assert_eq!(fs::read(&first).unwrap(), b"a");
+    }
+
+    #[test]
+    fn test_unwritable_path_is_left_out_of_batch() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let (home, trash, history) = setup();
+        let plain = write(&home.path().join("cafe.jpg"), b"a");
+        let latin1 = write(&home.path().join(std::ffi::OsStr::from_bytes(b"caf\xe9.jpg")), b"b");
+        let files = vec![trash.trash(&latin1).unwrap(), trash.trash(&plain).unwrap()];
+
+        assert_eq!(history.record(files).unwrap(), 1);
+        let (batch, report) = history.undo_last().unwrap().unwrap();
+        assert_eq!(batch.files.len(), 1);
+        assert!(report.is_complete());
+        assert_eq!(fs::read(&plain).unwrap(), b"a");
+        assert!(!latin1.exists());
+    }
+}
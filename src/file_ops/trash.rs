@@ -0,0 +1,276 @@
+//! Freedesktop.org Trash specification
+//!
+//! Files on the same filesystem as the home trash (`$XDG_DATA_HOME/Trash`) are
+//! moved there. Files on other mounts go to `$topdir/.Trash/$uid` when the
+//! administrator provides a sticky `$topdir/.Trash`, and to
+//! `$topdir/.Trash-$uid` otherwise, so a trashed file is never copied across
+//! devices. Every trashed file gets a `.trashinfo` record with its original
+//! path and deletion date, which file managers use to restore it.
+
+use crate::error::{DejaVuError, Result};
+use crate::file_ops::restore::TrashedFile;
+use crate::utils::numbered_name;
+use chrono::Local;
+use std::fs::{self, DirBuilder, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+/// Extension of the info records in `$trash/info`
+pub const INFO_EXTENSION: &str = "trashinfo";
+
+/// `DeletionDate` format: local time without a time zone
+pub const DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// A trash directory containing `files` and `info`
+#[derive(Debug, Clone)]
+struct TrashDir {
+    root: PathBuf,
+    /// Mount point that `Path=` entries are relative to; `None` for the home trash
+    topdir: Option<PathBuf>,
+}
+
+pub struct XdgTrash {
+    home_trash: PathBuf,
+    uid: u32,
+}
+
+impl XdgTrash {
+    /// Trash of the current user, `$XDG_DATA_HOME/Trash`
+    pub fn from_env() -> Result<Self> {
+        let data_dir = dirs::data_dir().ok_or_else(|| {
+            DejaVuError::FileOperationFailed("Cannot determine the XDG data directory".to_string())
+        })?;
+        Ok(Self::new(data_dir.join("Trash")))
+    }
+
+    /// Trash whose home trash directory is `home_trash`, e.g. `~/.local/share/Trash`
+    pub fn new(home_trash: PathBuf) -> Self {
+        // SAFETY: getuid has no preconditions and cannot fail
+        let uid = unsafe { libc::getuid() };
+        Self { home_trash, uid }
+    }
+
+    #[cfg(test)]
+    pub fn home_trash(&self) -> &Path {
+        &self.home_trash
+    }
+
+    /// Move `path` into the trash directory for its filesystem
+    pub fn trash(&self, path: &Path) -> Result<TrashedFile> {
+        let path = std::path::absolute(path).map_err(|e| trash_error(path, e))?;
+        let metadata = fs::symlink_metadata(&path).map_err(|e| trash_error(&path, e))?;
+
+        let trash_dir = self.trash_dir_for(&path, metadata.dev())?;
+        trash_dir.put(&path)
+    }
+
+    /// Pick the trash directory on the same device as the file
+    fn trash_dir_for(&self, path: &Path, dev: u64) -> Result<TrashDir> {
+        if nearest_device(&self.home_trash) == Some(dev) {
+            return Ok(TrashDir {
+                root: self.home_trash.clone(),
+                topdir: None,
+            });
+        }
+
+        let topdir = mount_point(path, dev);
+
+        // $topdir/.Trash/$uid, only if .Trash is a real directory with the sticky bit
+        let shared = topdir.join(".Trash");
+        if let Ok(metadata) = fs::symlink_metadata(&shared)
+            && metadata.is_dir()
+            && metadata.permissions().mode() & 0o1000 != 0
+        {
+            let root = shared.join(self.uid.to_string());
+            if create_private_dir(&root).is_ok() {
+                return Ok(TrashDir {
+                    root,
+                    topdir: Some(topdir),
+                });
+            }
+        }
+
+        let root = topdir.join(format!(".Trash-{}", self.uid));
+        create_private_dir(&root).map_err(|e| {
+            DejaVuError::FileOperationFailed(format!(
+                "No usable trash directory on the filesystem of {} ({}): {}",
+                path.display(),
+                root.display(),
+                e
+            ))
+        })?;
+        Ok(TrashDir {
+            root,
+            topdir: Some(topdir),
+        })
+    }
+}
+
+impl TrashDir {
+    fn put(&self, path: &Path) -> Result<TrashedFile> {
+        let files_dir = self.root.join("files");
+        let info_dir = self.root.join("info");
+        for dir in [&files_dir, &info_dir] {
+            create_private_dir(dir).map_err(|e| trash_error(path, e))?;
+        }
+
+        let name = path.file_name().ok_or_else(|| {
+            DejaVuError::FileOperationFailed(format!("Cannot trash {}: no file name", path.display()))
+        })?;
+
+        // Home trash records absolute paths; per-mount trashes record paths
+        // relative to the mount point so the disk can be mounted elsewhere
+        let recorded = match &self.topdir {
+            Some(topdir) => path.strip_prefix(topdir).unwrap_or(path),
+            None => path,
+        };
+        let record = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            encode_path(recorded),
+            Local::now().format(DATE_FORMAT)
+        );
+
+        // Creating the .trashinfo file exclusively reserves the name
+        for n in 1.. {
+            let trash_name = numbered_name(name, n);
+            let mut info_name = trash_name.clone();
+            info_name.push(".");
+            info_name.push(INFO_EXTENSION);
+            let info_path = info_dir.join(info_name);
+            let trashed_path = files_dir.join(&trash_name);
+
+            let mut info = match OpenOptions::new().write(true).create_new(true).open(&info_path) {
+                Ok(info) => info,
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(trash_error(path, e)),
+            };
+
+            // A leftover in files/ without an info record: keep looking
+            if fs::symlink_metadata(&trashed_path).is_ok() {
+                drop(info);
+                let _ = fs::remove_file(&info_path);
+                continue;
+            }
+
+            let moved = info
+                .write_all(record.as_bytes())
+                .and_then(|_| info.sync_all())
+                .and_then(|_| fs::rename(path, &trashed_path));
+            if let Err(e) = moved {
+                let _ = fs::remove_file(&info_path);
+                return Err(trash_error(path, e));
+            }
+
+            return Ok(TrashedFile {
+                original_path: path.to_path_buf(),
+                trashed_path,
+                info_path,
+            });
+        }
+        unreachable!("unbounded name search")
+    }
+}
+
+/// Device of `path`, or of its closest existing ancestor
+fn nearest_device(path: &Path) -> Option<u64> {
+    path.ancestors()
+        .find_map(|p| fs::metadata(p).ok())
+        .map(|m| m.dev())
+}
+
+/// Topmost ancestor of `path` that is still on device `dev`
+fn mount_point(path: &Path, dev: u64) -> PathBuf {
+    path.ancestors()
+        .skip(1)
+        .take_while(|p| fs::metadata(p).map(|m| m.dev() == dev).unwrap_or(false))
+        .last()
+        .unwrap_or(Path::new("/"))
+        .to_path_buf()
+}
+
+fn create_private_dir(path: &Path) -> std::io::Result<()> {
+    DirBuilder::new().recursive(true).mode(0o700).create(path)
+}
+
+/// Percent-encode a path for a `Path=` entry, keeping `/` and unreserved characters
+pub fn encode_path(path: &Path) -> String {
+    let mut encoded = String::new();
+    for &byte in path.as_os_str().as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn trash_error(path: &Path, e: std::io::Error) -> DejaVuError {
+    DejaVuError::FileOperationFailed(format!("Failed to move {} to trash: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// A temporary HOME with a file to trash
+    fn setup(name: &str) -> (TempDir, XdgTrash, PathBuf) {
+        let home = TempDir::new().unwrap();
+        let trash = XdgTrash::new(home.path().join(".local/share/Trash"));
+        let file = home.path().join("photos").join(name);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, b"pixels").unwrap();
+        (home, trash, file)
+    }
+
+    #[test]
+    fn test_trash_writes_file_and_info() {
+        let (_home, trash, file) = setup("my photo.jpg");
+
+        let trashed = trash.trash(&file).unwrap();
+
+        assert!(!file.exists());
+        assert_eq!(trashed.trashed_path, trash.home_trash().join("files/my photo.jpg"));
+        assert_eq!(fs::read(&trashed.trashed_path).unwrap(), b"pixels");
+        assert_eq!(trashed.info_path, trash.home_trash().join("info/my photo.jpg.trashinfo"));
+
+        let info = fs::read_to_string(&trashed.info_path).unwrap();
+        let lines: Vec<_> = info.lines().collect();
+        assert_eq!(lines[0], "[Trash Info]");
+        assert_eq!(lines[1], format!("Path={}", encode_path(&file)));
+        assert!(lines[1].ends_with("/photos/my%20photo.jpg"));
+        let date = lines[2].strip_prefix("DeletionDate=").unwrap();
+        assert!(chrono::NaiveDateTime::parse_from_str(date, DATE_FORMAT).is_ok());
+    }
+
+    #[test]
+    fn test_name_collisions_are_numbered() {
+        let (home, trash, first) = setup("a.jpg");
+        let second = home.path().join("a.jpg");
+        fs::write(&second, b"other").unwrap();
+
+        trash.trash(&first).unwrap();
+        let trashed = trash.trash(&second).unwrap();
+
+        assert_eq!(trashed.trashed_path, trash.home_trash().join("files/a.2.jpg"));
+        assert_eq!(trashed.info_path, trash.home_trash().join("info/a.2.jpg.trashinfo"));
+        assert_eq!(fs::read(&trashed.trashed_path).unwrap(), b"other");
+    }
+
+    #[test]
+    fn test_missing_file_leaves_no_record() {
+        let (_home, trash, file) = setup("a.jpg");
+        fs::remove_file(&file).unwrap();
+
+        assert!(trash.trash(&file).is_err());
+        assert!(!trash.home_trash().join("info/a.jpg.trashinfo").exists());
+    }
+
+    #[test]
+    fn test_encode_path() {
+        assert_eq!(encode_path(Path::new("/a b/100%/照片.jpg")), "/a%20b/100%25/%E7%85%A7%E7%89%87.jpg");
+    }
+}
@@ -0,0 +1,186 @@
+//! Pre-deletion safety checks
+//!
+//! Hashes are computed during the scan, possibly minutes before the user
+//! decides to delete something. Before a duplicate is removed, both it and the
+//! file being kept are re-checked against the state recorded by the scanner.
+
+use crate::error::{DejaVuError, Result};
+use crate::hashing::ExactHasher;
+use crate::models::{DuplicateGroup, FileInfo};
+use std::path::Path;
+
+/// Why a duplicate may no longer be safely removed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationFailure {
+    /// The file no longer exists
+    Vanished(String),
+    /// Size or modification time differs from the scan
+    Modified(String),
+    /// The duplicate and the kept file no longer have identical content
+    ContentMismatch,
+    /// Both entries refer to the same path
+    SamePath,
+}
+
+impl std::fmt::Display for VerificationFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerificationFailure::Vanished(path) => write!(f, "{} no longer exists", path),
+            VerificationFailure::Modified(path) => write!(f, "{} changed since the scan", path),
+            VerificationFailure::ContentMismatch => write!(f, "content no longer matches the kept original"),
+            VerificationFailure::SamePath => write!(f, "file and kept original are the same path"),
+        }
+    }
+}
+
+pub struct DuplicateVerifier;
+
+impl DuplicateVerifier {
+    /// Check that `duplicate` can be removed while `original` is kept
+    ///
+    /// Both files are re-stat'ed and must still match the size and modification
+    /// time recorded during the scan. With `compare_bytes` (exact groups), the two
+    /// files must also be identical byte for byte.
+    ///
+    /// Because the kept file has to exist, this also prevents deleting the last
+    /// remaining copy of a group.
+    pub fn verify(duplicate: &FileInfo, original: &FileInfo, compare_bytes: bool) -> Result<()> {
+        Self::check(duplicate, original, compare_bytes).map_err(|reason| {
+            DejaVuError::VerificationFailed(display(&duplicate.path), reason.to_string())
+        })
+    }
+
+    /// Check that no file of `group` changed or vanished since the scan
+    pub fn verify_group(group: &DuplicateGroup) -> Result<()> {
+        for file in &group.files {
+            Self::check_unchanged(file).map_err(|reason| {
+                DejaVuError::VerificationFailed(display(&file.path), reason.to_string())
+            })?;
+        }
+        Ok(())
+    }
+
+    fn check(
+        duplicate: &FileInfo,
+        original: &FileInfo,
+        compare_bytes: bool,
+    ) -> std::result::Result<(), VerificationFailure> {
+        if duplicate.path == original.path {
+            return Err(VerificationFailure::SamePath);
+        }
+
+        Self::check_unchanged(duplicate)?;
+        Self::check_unchanged(original)?;
+
+        if compare_bytes {
+            match ExactHasher::files_identical(&duplicate.path, &original.path) {
+                Ok(true) => {}
+                Ok(false) => return Err(VerificationFailure::ContentMismatch),
+                Err(_) => return Err(Self::vanished_or_modified(duplicate, original)),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Re-stat a file and compare it with the scanned metadata
+    fn check_unchanged(file: &FileInfo) -> std::result::Result<(), VerificationFailure> {
+        let path = display(&file.path);
+        let metadata = match std::fs::symlink_metadata(&file.path) {
+            Ok(metadata) => metadata,
+            Err(_) => return Err(VerificationFailure::Vanished(path)),
+        };
+
+        let modified = metadata.modified().ok();
+        if !metadata.is_file() || metadata.len() != file.size || modified != Some(file.modified) {
+            return Err(VerificationFailure::Modified(path));
+        }
+        Ok(())
+    }
+
+    /// Explain a read failure during the byte comparison
+    fn vanished_or_modified(duplicate: &FileInfo, original: &FileInfo) -> VerificationFailure {
+        [duplicate, original]
+            .iter()
+            .find_map(|f| Self::check_unchanged(f).err())
+            .unwrap_or_else(|| VerificationFailure::Modified(display(&duplicate.path)))
+    }
+}
+
+fn display(path: &Path) -> String {
+    path.display().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn failure(duplicate: &FileInfo, original: &FileInfo) -> VerificationFailure {
+        DuplicateVerifier::check(duplicate, original, true).unwrap_err()
+    }
+
+    #[test]
+    fn test_identical_files_pass() {
+        let dir = TempDir::new().unwrap();
+        let a = dir.path().join("a.jpg");
+        let b = dir.path().join("b.jpg");
+        std::fs::write(&a, b"same bytes").unwrap();
+        std::fs::write(&b, b"same bytes").unwrap();
+
+        assert!(DuplicateVerifier::verify(&FileInfo::scanned(&b), &FileInfo::scanned(&a), true).is_ok());
+    }
+
+    #[test]
+    fn test_vanished_original_is_refused() {
+        let dir = TempDir::new().unwrap();
+        let a = dir.path().join("a.jpg");
+        let b = dir.path().join("b.jpg");
+        std::fs::write(&a, b"same bytes").unwrap();
+        std::fs::write(&b, b"same bytes").unwrap();
+        let original = FileInfo::scanned(&a);
+        std::fs::remove_file(&a).unwrap();
+
+        assert_eq!(failure(&FileInfo::scanned(&b), &original), VerificationFailure::Vanished(display(&a)));
+    }
+
+    #[test]
+    fn test_modified_duplicate_is_refused() {
+        let dir = TempDir::new().unwrap();
+        let a = dir.path().join("a.jpg");
+        let b = dir.path().join("b.jpg");
+        std::fs::write(&a, b"same bytes").unwrap();
+        std::fs::write(&b, b"same bytes").unwrap();
+        let duplicate = FileInfo::scanned(&b);
+        std::fs::write(&b, b"different length").unwrap();
+
+        assert_eq!(failure(&duplicate, &FileInfo::scanned(&a)), VerificationFailure::Modified(display(&b)));
+    }
+
+    #[test]
+    fn test_content_mismatch_is_refused() {
+        let dir = TempDir::new().unwrap();
+        let a = dir.path().join("a.jpg");
+        let b = dir.path().join("b.jpg");
+        std::fs::write(&a, b"same bytes").unwrap();
+        std::fs::write(&b, b"s4me bytes").unwrap();
+
+        assert_eq!(failure(&FileInfo::scanned(&b), &FileInfo::scanned(&a)), VerificationFailure::ContentMismatch);
+        // Similar groups only re-stat the files
+        assert!(DuplicateVerifier::verify(&FileInfo::scanned(&b), &FileInfo::scanned(&a), false).is_ok());
+    }
+
+    #[test]
+    fn test_group_with_a_changed_file_is_refused() {
+        let dir = TempDir::new().unwrap();
+        let a = dir.path().join("a.jpg");
+        let b = dir.path().join("b.jpg");
+        std::fs::write(&a, b"same bytes").unwrap();
+        std::fs::write(&b, b"same bytes").unwrap();
+        let group = DuplicateGroup::new(0, vec![FileInfo::scanned(&a), FileInfo::scanned(&b)]);
+        assert!(DuplicateVerifier::verify_group(&group).is_ok());
+
+        std::fs::write(&a, b"different length").unwrap();
+        assert!(DuplicateVerifier::verify_group(&group).is_err());
+    }
+}
@@ -1,19 +1,100 @@
 use crate::error::Result;
 use sha2::{Digest, Sha256};
 use std::fs::File;
-use std::io::Read;
+use std::io::{Read, Seek, SeekFrom};
 use std::path::Path;
+use xxhash_rust::xxh3::Xxh3;
 
-pub struct ExactHasher;
+/// Bytes read from each end of a file for the partial hash
+pub const PARTIAL_HASH_BYTES: u64 = 16 * 1024;
+
+/// Content hash algorithms for exact duplicate detection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ExactHashAlgorithm {
+    /// SHA-256: cryptographic, slowest
+    #[default]
+    #[value(name = "sha256")]
+    Sha256,
+    /// BLAKE3: cryptographic and much faster than SHA-256
+    #[value(name = "blake3")]
+    Blake3,
+    /// xxh3-128: non-cryptographic, fastest
+    #[value(name = "xxh3")]
+    Xxh3,
+}
+
+impl ExactHashAlgorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExactHashAlgorithm::Sha256 => "SHA-256",
+            ExactHashAlgorithm::Blake3 => "BLAKE3",
+            ExactHashAlgorithm::Xxh3 => "xxh3-128",
+        }
+    }
+
+    fn streaming(&self) -> StreamingHasher {
+        match self {
+            ExactHashAlgorithm::Sha256 => StreamingHasher::Sha256(Sha256::new()),
+            ExactHashAlgorithm::Blake3 => StreamingHasher::Blake3(Box::new(blake3::Hasher::new())),
+            ExactHashAlgorithm::Xxh3 => StreamingHasher::Xxh3(Box::new(Xxh3::new())),
+        }
+    }
+}
+
+impl std::fmt::Display for ExactHashAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Incremental hasher state for the selected algorithm
+enum StreamingHasher {
+    Sha256(Sha256),
+    Blake3(Box<blake3::Hasher>),
+    Xxh3(Box<Xxh3>),
+}
+
+impl StreamingHasher {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            StreamingHasher::Sha256(h) => h.update(data),
+            StreamingHasher::Blake3(h) => {
+                h.update(data);
+            }
+            StreamingHasher::Xxh3(h) => h.update(data),
+        }
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        match self {
+            StreamingHasher::Sha256(h) => h.finalize().to_vec(),
+            StreamingHasher::Blake3(h) => h.finalize().as_bytes().to_vec(),
+            StreamingHasher::Xxh3(h) => h.digest128().to_be_bytes().to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExactHasher {
+    algorithm: ExactHashAlgorithm,
+}
 
 impl ExactHasher {
-    /// Compute SHA-256 hash of a file
+    pub fn new(algorithm: ExactHashAlgorithm) -> Self {
+        Self { algorithm }
+    }
+
+    pub fn algorithm(&self) -> ExactHashAlgorithm {
+        self.algorithm
+    }
+
+    /// Compute the content hash of a file
     /// Reads file in 64KB chunks to avoid loading entire file into memory
-    pub fn compute_hash(path: &Path) -> Result<Vec<u8>> {
+    pub fn compute_hash(&self, path: &Path) -> Result<Vec<u8>> {
         let file = File::open(path)?;
         let mut reader = std::io::BufReader::with_capacity(65536, file);
 
-        let mut hasher = Sha256::new();
+        let mut hasher = self.algorithm.streaming();
         let mut buffer = [0u8; 65536]; // 64KB buffer
 
         loop {
@@ -24,20 +105,60 @@ impl ExactHasher {
             hasher.update(&buffer[..n]);
         }
 
-        Ok(hasher.finalize().to_vec())
+        Ok(hasher.finalize())
     }
 
-    /// Compute hash as hex string
-    pub fn compute_hash_string(path: &Path) -> Result<String> {
-        let hash = Self::compute_hash(path)?;
-        Ok(hex::encode(hash))
+    /// Compute the hash of the first and last PARTIAL_HASH_BYTES of a file
+    /// Files no larger than 2 * PARTIAL_HASH_BYTES are hashed in full, so for
+    /// them the partial hash equals `compute_hash`
+    pub fn compute_partial_hash(&self, path: &Path, size: u64) -> Result<Vec<u8>> {
+        if Self::partial_covers_file(size) {
+            return self.compute_hash(path);
+        }
+
+        let mut file = File::open(path)?;
+        let mut hasher = self.algorithm.streaming();
+        let mut buffer = vec![0u8; PARTIAL_HASH_BYTES as usize];
+
+        file.read_exact(&mut buffer)?;
+        hasher.update(&buffer);
+
+        file.seek(SeekFrom::Start(size - PARTIAL_HASH_BYTES))?;
+        file.read_exact(&mut buffer)?;
+        hasher.update(&buffer);
+
+        Ok(hasher.finalize())
+    }
+
+    /// Whether `compute_partial_hash` reads the whole file for this size
+    pub fn partial_covers_file(size: u64) -> bool {
+        size <= 2 * PARTIAL_HASH_BYTES
     }
 
-    /// Check if two files have the same hash without storing the full hash
-    pub fn files_equal(path1: &Path, path2: &Path) -> Result<bool> {
-        let hash1 = Self::compute_hash(path1)?;
-        let hash2 = Self::compute_hash(path2)?;
-        Ok(hash1 == hash2)
+    /// Compare two files byte for byte, streaming both in 64KB chunks
+    pub fn files_identical(path1: &Path, path2: &Path) -> Result<bool> {
+        let file1 = File::open(path1)?;
+        let file2 = File::open(path2)?;
+        if file1.metadata()?.len() != file2.metadata()?.len() {
+            return Ok(false);
+        }
+
+        let mut reader1 = std::io::BufReader::with_capacity(65536, file1);
+        let mut reader2 = std::io::BufReader::with_capacity(65536, file2);
+        let mut buffer1 = [0u8; 65536];
+        let mut buffer2 = [0u8; 65536];
+
+        loop {
+            let n = reader1.read(&mut buffer1)?;
+            if n == 0 {
+                // Both files have the same length, so the second one is exhausted too
+                return Ok(true);
+            }
+            reader2.read_exact(&mut buffer2[..n])?;
+            if buffer1[..n] != buffer2[..n] {
+                return Ok(false);
+            }
+        }
     }
 }
 
@@ -52,18 +173,75 @@ mod tests {
         let mut temp_file = NamedTempFile::new().unwrap();
         temp_file.write_all(b"Hello, World!").unwrap();
 
-        let hash = ExactHasher::compute_hash(temp_file.path()).unwrap();
+        let hash = ExactHasher::default().compute_hash(temp_file.path()).unwrap();
         assert_eq!(hash.len(), 32); // SHA-256 produces 32 bytes
     }
 
     #[test]
-    fn test_files_equal() {
+    fn test_same_content_same_hash() {
+        let mut temp_file1 = NamedTempFile::new().unwrap();
+        temp_file1.write_all(b"Same content").unwrap();
+
+        let mut temp_file2 = NamedTempFile::new().unwrap();
+        temp_file2.write_all(b"Same content").unwrap();
+
+        let hasher = ExactHasher::default();
+        assert_eq!(hasher.compute_hash(temp_file1.path()).unwrap(), hasher.compute_hash(temp_file2.path()).unwrap());
+    }
+
+    #[test]
+    fn test_partial_hash_ignores_middle() {
+        let size = PARTIAL_HASH_BYTES as usize * 3;
+        let mut content = vec![7u8; size];
+
+        let mut temp_file1 = NamedTempFile::new().unwrap();
+        temp_file1.write_all(&content).unwrap();
+
+        content[size / 2] = 8;
+        let mut temp_file2 = NamedTempFile::new().unwrap();
+        temp_file2.write_all(&content).unwrap();
+
+        let partial1 = ExactHasher::default().compute_partial_hash(temp_file1.path(), size as u64).unwrap();
+        let partial2 = ExactHasher::default().compute_partial_hash(temp_file2.path(), size as u64).unwrap();
+        assert_eq!(partial1, partial2);
+        let full1 = ExactHasher::default().compute_hash(temp_file1.path()).unwrap();
+        let full2 = ExactHasher::default().compute_hash(temp_file2.path()).unwrap();
+        assert_ne!(full1, full2);
+    }
+
+    #[test]
+    fn test_partial_hash_of_small_file_is_full_hash() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"Small file").unwrap();
+
+        let partial = ExactHasher::default().compute_partial_hash(temp_file.path(), 10).unwrap();
+        assert_eq!(partial, ExactHasher::default().compute_hash(temp_file.path()).unwrap());
+    }
+
+    #[test]
+    fn test_algorithms_produce_expected_digest_lengths() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"Hello, World!").unwrap();
+
+        let blake3 = ExactHasher::new(ExactHashAlgorithm::Blake3).compute_hash(temp_file.path()).unwrap();
+        let xxh3 = ExactHasher::new(ExactHashAlgorithm::Xxh3).compute_hash(temp_file.path()).unwrap();
+        assert_eq!(blake3.len(), 32);
+        assert_eq!(xxh3.len(), 16);
+        assert_eq!(blake3, blake3::hash(b"Hello, World!").as_bytes().to_vec());
+    }
+
+    #[test]
+    fn test_files_identical() {
         let mut temp_file1 = NamedTempFile::new().unwrap();
         temp_file1.write_all(b"Same content").unwrap();
 
         let mut temp_file2 = NamedTempFile::new().unwrap();
         temp_file2.write_all(b"Same content").unwrap();
 
-        assert!(ExactHasher::files_equal(temp_file1.path(), temp_file2.path()).unwrap());
+        let mut temp_file3 = NamedTempFile::new().unwrap();
+        temp_file3.write_all(b"Same c0ntent").unwrap();
+
+        assert!(ExactHasher::files_identical(temp_file1.path(), temp_file2.path()).unwrap());
+        assert!(!ExactHasher::files_identical(temp_file1.path(), temp_file3.path()).unwrap());
     }
 }
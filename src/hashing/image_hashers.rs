@@ -0,0 +1,247 @@
+//! Perceptual hash algorithms
+//!
+//! Each algorithm reduces a grayscale image to a `hash_size * hash_size` bit
+//! fingerprint stored in a u64, so hashes can be compared by Hamming distance.
+
+use image::imageops::FilterType;
+use image::GrayImage;
+use std::f64::consts::PI;
+
+/// Ratio between the resized image side and the hash side (32x32 -> 8x8 for the default size)
+const RESIZE_FACTOR: u32 = 4;
+
+/// Supported perceptual hash algorithms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum HashAlgorithm {
+    /// Average hash: fastest, sensitive to brightness changes
+    #[value(name = "ahash")]
+    Average,
+    /// Difference hash: fast, robust against re-encoding
+    #[value(name = "dhash")]
+    Difference,
+    /// DCT hash: robust against brightness and contrast changes
+    #[default]
+    #[value(name = "phash")]
+    Perceptual,
+    /// Haar wavelet hash: more tolerant of crops
+    #[value(name = "whash")]
+    Wavelet,
+}
+
+impl HashAlgorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Average => "aHash",
+            HashAlgorithm::Difference => "dHash",
+            HashAlgorithm::Perceptual => "pHash",
+            HashAlgorithm::Wavelet => "wHash",
+        }
+    }
+
+    /// Get the hasher implementing this algorithm
+    pub fn hasher(&self) -> &'static dyn ImageHash {
+        match self {
+            HashAlgorithm::Average => &AverageHash,
+            HashAlgorithm::Difference => &DifferenceHash,
+            HashAlgorithm::Perceptual => &DctHash,
+            HashAlgorithm::Wavelet => &WaveletHash,
+        }
+    }
+}
+
+impl std::fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A perceptual hash algorithm over grayscale images
+pub trait ImageHash: Send + Sync {
+    /// Hash `img` into `hash_size * hash_size` bits (hash_size is 1-8)
+    fn hash(&self, img: &GrayImage, hash_size: usize) -> u64;
+}
+
+/// aHash: downscale to hash_size² and compare every pixel with the mean
+pub struct AverageHash;
+
+impl ImageHash for AverageHash {
+    fn hash(&self, img: &GrayImage, hash_size: usize) -> u64 {
+        let side = hash_size as u32;
+        let resized = image::imageops::resize(img, side, side, FilterType::Triangle);
+        let pixels: Vec<f64> = resized.pixels().map(|p| p.0[0] as f64).collect();
+
+        let mean = pixels.iter().sum::<f64>() / pixels.len() as f64;
+        bits_above(&pixels, mean)
+    }
+}
+
+/// dHash: downscale to (hash_size + 1) x hash_size and compare horizontal neighbours
+pub struct DifferenceHash;
+
+impl ImageHash for DifferenceHash {
+    fn hash(&self, img: &GrayImage, hash_size: usize) -> u64 {
+        let side = hash_size as u32;
+        let resized = image::imageops::resize(img, side + 1, side, FilterType::Triangle);
+
+        let mut hash = 0u64;
+        for y in 0..side {
+            for x in 0..side {
+                let left = resized.get_pixel(x, y).0[0];
+                let right = resized.get_pixel(x + 1, y).0[0];
+                if left < right {
+                    hash |= 1 << (y * side + x);
+                }
+            }
+        }
+        hash
+    }
+}
+
+/// pHash: 2D DCT-II of a (4 * hash_size)² thumbnail, low frequencies against their median
+pub struct DctHash;
+
+impl ImageHash for DctHash {
+    fn hash(&self, img: &GrayImage, hash_size: usize) -> u64 {
+        let side = hash_size * RESIZE_FACTOR as usize;
+        let pixels = resized_pixels(img, side);
+        let dct = dct_2d(&pixels, side);
+
+        // Keep the low-frequency corner of the DCT
+        let coefficients = top_left(&dct, side, hash_size);
+
+        // The DC term only reflects average brightness, so leave it out of the median
+        let median = median(&coefficients[1..]).unwrap_or(coefficients[0]);
+        bits_above(&coefficients, median)
+    }
+}
+
+/// wHash: Haar wavelet low-pass band of a (4 * hash_size)² thumbnail against its median
+///
+/// The image mean is removed first, which is the same as dropping the single
+/// lowest-frequency coefficient of a full Haar decomposition.
+pub struct WaveletHash;
+
+impl ImageHash for WaveletHash {
+    fn hash(&self, img: &GrayImage, hash_size: usize) -> u64 {
+        let side = hash_size * RESIZE_FACTOR as usize;
+        let mut pixels = resized_pixels(img, side);
+
+        let mean = pixels.iter().sum::<f64>() / pixels.len() as f64;
+        pixels.iter_mut().for_each(|p| *p -= mean);
+
+        // Each level halves the low-pass band: 4N -> 2N -> N
+        let mut size = side;
+        while size > hash_size {
+            haar_step(&mut pixels, side, size);
+            size /= 2;
+        }
+
+        let low_pass = top_left(&pixels, side, hash_size);
+        let median = median(&low_pass).unwrap_or(0.0);
+        bits_above(&low_pass, median)
+    }
+}
+
+fn resized_pixels(img: &GrayImage, side: usize) -> Vec<f64> {
+    let side = side as u32;
+    image::imageops::resize(img, side, side, FilterType::Triangle)
+        .pixels()
+        .map(|p| p.0[0] as f64)
+        .collect()
+}
+
+/// Set bit i for every value strictly above the threshold
+fn bits_above(values: &[f64], threshold: f64) -> u64 {
+    values
+        .iter()
+        .enumerate()
+        .filter(|&(_, &v)| v > threshold)
+        .fold(0u64, |hash, (i, _)| hash | (1 << i))
+}
+
+/// Copy the `size x size` top-left block of a row-major `stride`-wide matrix
+fn top_left(values: &[f64], stride: usize, size: usize) -> Vec<f64> {
+    let mut block = Vec::with_capacity(size * size);
+    for y in 0..size {
+        block.extend_from_slice(&values[y * stride..y * stride + size]);
+    }
+    block
+}
+
+/// One 2D Haar level over the top-left `size x size` block, low-pass band ends up top-left
+fn haar_step(values: &mut [f64], stride: usize, size: usize) {
+    let half = size / 2;
+    let mut buffer = vec![0.0; size];
+
+    // Rows
+    for y in 0..size {
+        let row = &mut values[y * stride..y * stride + size];
+        for i in 0..half {
+            buffer[i] = (row[2 * i] + row[2 * i + 1]) / std::f64::consts::SQRT_2;
+            buffer[half + i] = (row[2 * i] - row[2 * i + 1]) / std::f64::consts::SQRT_2;
+        }
+        row.copy_from_slice(&buffer);
+    }
+
+    // Columns
+    for x in 0..size {
+        for i in 0..half {
+            let a = values[2 * i * stride + x];
+            let b = values[(2 * i + 1) * stride + x];
+            buffer[i] = (a + b) / std::f64::consts::SQRT_2;
+            buffer[half + i] = (a - b) / std::f64::consts::SQRT_2;
+        }
+        for (y, value) in buffer.iter().enumerate() {
+            values[y * stride + x] = *value;
+        }
+    }
+}
+
+/// Separable 2D DCT-II over a square `n x n` block stored row-major
+fn dct_2d(input: &[f64], n: usize) -> Vec<f64> {
+    // Precompute the cosine table: cos[(2x + 1) * u * PI / 2n]
+    let mut table = vec![0.0; n * n];
+    for u in 0..n {
+        for x in 0..n {
+            table[u * n + x] = ((2 * x + 1) as f64 * u as f64 * PI / (2 * n) as f64).cos();
+        }
+    }
+
+    let dct_1d = |get: &dyn Fn(usize) -> f64, out: &mut [f64]| {
+        for (u, value) in out.iter_mut().enumerate() {
+            *value = (0..n).map(|x| get(x) * table[u * n + x]).sum();
+        }
+    };
+
+    // Rows first
+    let mut rows = vec![0.0; n * n];
+    for y in 0..n {
+        dct_1d(&|x| input[y * n + x], &mut rows[y * n..(y + 1) * n]);
+    }
+
+    // Then columns
+    let mut output = vec![0.0; n * n];
+    let mut column = vec![0.0; n];
+    for x in 0..n {
+        dct_1d(&|y| rows[y * n + x], &mut column);
+        for (y, value) in column.iter().enumerate() {
+            output[y * n + x] = *value;
+        }
+    }
+
+    output
+}
+
+fn median(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        Some((sorted[mid - 1] + sorted[mid]) / 2.0)
+    } else {
+        Some(sorted[mid])
+    }
+}
@@ -1,5 +1,7 @@
 pub mod exact_hash;
+pub mod image_hashers;
 pub mod perceptual_hash;
 
-pub use exact_hash::ExactHasher;
+pub use exact_hash::{ExactHashAlgorithm, ExactHasher};
+pub use image_hashers::HashAlgorithm;
 pub use perceptual_hash::PerceptualHasher;
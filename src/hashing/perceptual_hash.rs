@@ -1,28 +1,54 @@
-use crate::error::Result;
+use crate::error::{DejaVuError, Result};
+use crate::hashing::image_hashers::HashAlgorithm;
+use image::GrayImage;
 use std::path::Path;
 
 pub struct PerceptualHasher {
     hash_size: u8,
+    algorithm: HashAlgorithm,
 }
 
 impl PerceptualHasher {
     pub fn new() -> Self {
-        Self { hash_size: 8 }
+        Self::with_size(8)
     }
 
+    /// hash_size: side length of the block kept for the hash (1-8)
+    /// The resulting hash uses hash_size * hash_size bits of the u64
     pub fn with_size(hash_size: u8) -> Self {
-        Self { hash_size }
+        Self {
+            hash_size,
+            algorithm: HashAlgorithm::default(),
+        }
     }
 
-    /// Compute perceptual hash of an image
+    pub fn with_algorithm(mut self, algorithm: HashAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// Compute perceptual hash of an image with the configured algorithm
     /// Returns a 64-bit hash (for 8x8 hash)
-    /// Note: Simplified implementation using only exact hash for now
-    /// Full perceptual hashing requires complex image processing
-    pub fn compute_hash(&self, _path: &Path) -> Result<u64> {
-        // For now, return a dummy hash
-        // TODO: Implement proper perceptual hashing or use a different library
-        // that's compatible with image 0.25
-        Ok(0)
+    pub fn compute_hash(&self, path: &Path) -> Result<u64> {
+        let img = image::open(path)?;
+        self.hash_image(&img.to_luma8())
+    }
+
+    /// Compute perceptual hash of an already decoded grayscale image
+    pub fn hash_image(&self, img: &GrayImage) -> Result<u64> {
+        let hash_size = self.hash_size as usize;
+        if hash_size == 0 || hash_size * hash_size > 64 {
+            return Err(DejaVuError::HashError(format!(
+                "Unsupported perceptual hash size {} (expected 1-8)",
+                self.hash_size
+            )));
+        }
+
+        Ok(self.algorithm.hasher().hash(img, hash_size))
     }
 
     /// Compute Hamming distance between two perceptual hashes
@@ -47,6 +73,49 @@ impl Default for PerceptualHasher {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use clap::ValueEnum;
+    use image::codecs::jpeg::JpegEncoder;
+    use image::imageops::FilterType;
+    use image::{Rgb, RgbImage};
+    use std::fs::File;
+    use tempfile::TempDir;
+
+    /// Default `--threshold` value from the CLI
+    const DEFAULT_THRESHOLD: u32 = 5;
+
+    fn landscape(width: u32, height: u32) -> RgbImage {
+        RgbImage::from_fn(width, height, |x, y| {
+            let fx = x as f32 / width as f32;
+            let fy = y as f32 / height as f32;
+            // Sky gradient, a sun and a dark hill
+            let in_sun = (fx - 0.7).powi(2) + (fy - 0.25).powi(2) < 0.01;
+            let in_hill = fy > 0.6 + 0.15 * (fx * 6.0).sin();
+            if in_sun {
+                Rgb([250, 220, 60])
+            } else if in_hill {
+                Rgb([30, (90.0 + 60.0 * fx) as u8, 40])
+            } else {
+                Rgb([(80.0 + 100.0 * fy) as u8, (140.0 + 80.0 * fy) as u8, 230])
+            }
+        })
+    }
+
+    fn checkerboard(width: u32, height: u32) -> RgbImage {
+        RgbImage::from_fn(width, height, |x, y| {
+            if ((x / 40) + (y / 40)) % 2 == 0 {
+                Rgb([240, 240, 240])
+            } else {
+                Rgb([15, 15, 15])
+            }
+        })
+    }
+
+    fn save_jpeg(img: &RgbImage, path: &Path, quality: u8) {
+        let file = File::create(path).unwrap();
+        JpegEncoder::new_with_quality(file, quality)
+            .encode_image(img)
+            .unwrap();
+    }
 
     #[test]
     fn test_hamming_distance() {
@@ -68,4 +137,67 @@ mod tests {
         assert!(PerceptualHasher::are_similar(0b1010, 0b1001, 2)); // 2 bits diff
         assert!(!PerceptualHasher::are_similar(0b1010, 0b0001, 2)); // 3 bits diff
     }
+
+    #[test]
+    fn test_resized_jpeg_is_similar() {
+        let dir = TempDir::new().unwrap();
+        let original = dir.path().join("original.jpg");
+        let resized = dir.path().join("resized.jpg");
+
+        let img = landscape(640, 480);
+        save_jpeg(&img, &original, 95);
+        let small = image::imageops::resize(&img, 200, 150, FilterType::Lanczos3);
+        save_jpeg(&small, &resized, 40);
+
+        for &algorithm in HashAlgorithm::value_variants() {
+            let hasher = PerceptualHasher::new().with_algorithm(algorithm);
+            let hash1 = hasher.compute_hash(&original).unwrap();
+            let hash2 = hasher.compute_hash(&resized).unwrap();
+
+            assert!(
+                PerceptualHasher::are_similar(hash1, hash2, DEFAULT_THRESHOLD),
+                "{}: distance {} exceeds threshold",
+                algorithm,
+                PerceptualHasher::hamming_distance(hash1, hash2)
+            );
+        }
+    }
+
+    #[test]
+    fn test_unrelated_image_is_not_similar() {
+        let dir = TempDir::new().unwrap();
+        let first = dir.path().join("landscape.jpg");
+        let second = dir.path().join("checkerboard.jpg");
+
+        save_jpeg(&landscape(640, 480), &first, 90);
+        save_jpeg(&checkerboard(640, 480), &second, 90);
+
+        for &algorithm in HashAlgorithm::value_variants() {
+            let hasher = PerceptualHasher::new().with_algorithm(algorithm);
+            let hash1 = hasher.compute_hash(&first).unwrap();
+            let hash2 = hasher.compute_hash(&second).unwrap();
+
+            assert!(
+                !PerceptualHasher::are_similar(hash1, hash2, DEFAULT_THRESHOLD),
+                "{}: unrelated images are within the threshold",
+                algorithm
+            );
+        }
+    }
+
+    #[test]
+    fn test_hash_size_limits_bits() {
+        let img = image::DynamicImage::ImageRgb8(landscape(128, 128)).to_luma8();
+
+        for &algorithm in HashAlgorithm::value_variants() {
+            let hash = PerceptualHasher::with_size(4)
+                .with_algorithm(algorithm)
+                .hash_image(&img)
+                .unwrap();
+            assert_eq!(hash >> 16, 0, "{} uses more than 16 bits", algorithm);
+        }
+
+        assert!(PerceptualHasher::with_size(0).hash_image(&img).is_err());
+        assert!(PerceptualHasher::with_size(9).hash_image(&img).is_err());
+    }
 }
@@ -1,9 +1,11 @@
+mod cache;
 mod cli;
 mod dedup;
 mod error;
 mod file_ops;
 mod hashing;
 mod models;
+mod report;
 mod scanner;
 mod tui;
 mod utils;
@@ -12,42 +14,258 @@ use crate::tui::KeyAction;
 use crate::utils::format_size;
 use clap::Parser as _;
 
-use crate::cli::Args;
-use crate::dedup::HashGrouper;
+use crate::cache::HashCache;
+use crate::cli::{Args, Command, ScanArgs};
+use crate::dedup::{HashGrouper, KeepPolicy, removal_locations};
 use crate::error::{DejaVuError, Result};
-use crate::file_ops::{FileDeleter, FileOpener};
-use crate::models::DuplicateGroup;
+use crate::file_ops::executor::{ActionOutcome, ActionSummary};
+use crate::file_ops::journal::Outcome;
+use crate::file_ops::json_lines::JsonLines;
+use crate::file_ops::restore::{RestoreReport, TrashBatch};
+use crate::file_ops::verifier::DuplicateVerifier;
+use crate::file_ops::{
+    ActionExecutor, FileAction, FileOpener, Journal, LinkKind, Quarantine, TrashHistory, TrashedFile,
+};
+use crate::models::{DuplicateGroup, FileId};
+use crate::report::{ReportGenerator, ScriptAction, ScriptReport};
 use crate::scanner::{FileCollector, MediaFilter};
 use crate::tui::event::handle_key_event;
-use crate::tui::{App, MainLayout};
+use crate::tui::graphics::encode::{KITTY_DELETE_ALL, kitty_delete};
+use crate::tui::graphics::{CellSize, GraphicsProtocol, Preview, PreviewImage, PreviewLoader, PreviewSlot};
+use crate::tui::{App, MainLayout, Operation, StatusKind};
 use crossterm::{
+    cursor::MoveTo,
     event::{self, DisableMouseCapture, EnableMouseCapture},
-    execute,
+    execute, queue,
+    style::Print,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use indicatif::{ProgressBar, ProgressStyle};
 use ratatui::{
     Terminal,
     backend::{Backend, CrosstermBackend},
+    layout::Rect,
 };
-use std::io;
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
 use std::time::Duration;
 
+/// Exit status when a scan finished without finding duplicates
+const EXIT_CLEAN: u8 = 0;
+/// Exit status when duplicates were found
+const EXIT_DUPLICATES: u8 = 1;
+/// Exit status for any error
+const EXIT_ERROR: u8 = 2;
+
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     let args = Args::parse();
 
+    let result = match &args.command {
+        Some(Command::Restore) => restore_last_batch(args.dry_run),
+        Some(Command::Log { verbose, limit }) => show_log(*verbose, *limit),
+        Some(Command::Apply {
+            directory,
+            scan,
+            keep,
+            action,
+            symlink_target,
+            include_similar,
+            yes,
+        }) => apply(
+            directory,
+            scan,
+            *keep,
+            action.file_action(*symlink_target),
+            *include_similar,
+            *yes,
+            args.dry_run,
+        ),
+        None => run(&args),
+    };
+
+    match result {
+        Ok(code) => ExitCode::from(code),
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+/// Scan, group and then either report or launch the TUI
+///
+/// Status messages go to stderr so that a report on stdout stays parseable.
+fn run(args: &Args) -> Result<u8> {
+    let report_format = args.report_format();
+    let directory = args
+        .directory
+        .as_deref()
+        .expect("clap requires DIRECTORY without a subcommand");
+
+    let duplicate_groups = find_duplicates(&args.scan, directory)?;
+    let exit_code = if duplicate_groups.is_empty() {
+        EXIT_CLEAN
+    } else {
+        EXIT_DUPLICATES
+    };
+
+    // Step 3: Report, script or launch TUI
+    if let Some(format) = report_format {
+        write_output(args.output.as_deref(), |out| {
+            ReportGenerator::write(format, &duplicate_groups, directory, out)
+        })?;
+        return Ok(exit_code);
+    }
+    if let Some(action) = args.script {
+        let script = script_report(action, args.scan.quarantine.clone());
+        write_output(args.output.as_deref(), |out| script.write(&duplicate_groups, out))?;
+        return Ok(exit_code);
+    }
+
+    if duplicate_groups.is_empty() {
+        return Ok(EXIT_CLEAN);
+    }
+
+    // The duplicate status is for scripts; an interactive session that ended
+    // normally exits cleanly
+    println!("\n🚀 正在启动图形界面...");
+    println!("💡 提示: 按 ? 键可查看帮助");
+    let app = App::new(duplicate_groups)
+        .with_symlink_target(args.symlink_target)
+        .with_quarantine(args.scan.quarantine.clone())
+        .with_dry_run(args.dry_run)
+        .with_preview(args.preview.protocol());
+    let mut executor = ActionExecutor::new().with_dry_run(args.dry_run);
+    if let Some(journal) = open_journal() {
+        executor = executor.with_journal(journal);
+    }
+    if let Some(quarantine) = &args.scan.quarantine {
+        executor = executor.with_quarantine(Quarantine::new(quarantine.clone()));
+    }
+    run_tui(app, &executor)?;
+    if executor.is_dry_run() {
+        print_dry_run_summary(executor.summary());
+    }
+
+    Ok(EXIT_CLEAN)
+}
+
+/// `dejavu apply`: keep one file per group by `keep` and act on the others,
+/// unattended
+///
+/// Refuses to touch anything unless `yes` (or `dry_run`) is set and every
+/// group is still exactly as scanned.
+fn apply(
+    directory: &Path,
+    scan: &ScanArgs,
+    keep: KeepPolicy,
+    action: FileAction,
+    include_similar: bool,
+    yes: bool,
+    dry_run: bool,
+) -> Result<u8> {
+    let mut groups = find_duplicates(scan, directory)?;
+    if !include_similar {
+        groups.retain(|g| g.is_exact_duplicate());
+    }
+    for group in &mut groups {
+        keep.apply(group);
+    }
+
+    let locations = removal_locations(&groups);
+    if locations.is_empty() {
+        return Ok(EXIT_CLEAN);
+    }
+
+    let diverged: Vec<DejaVuError> = groups
+        .iter()
+        .filter_map(|group| DuplicateVerifier::verify_group(group).err())
+        .collect();
+    if !diverged.is_empty() {
+        for e in &diverged {
+            eprintln!("❌ {}", e);
+        }
+        return Err(DejaVuError::FileOperationFailed(format!(
+            "{} groups changed since the scan; nothing was done",
+            diverged.len()
+        )));
+    }
+
+    let planned_bytes: u64 = locations.iter().map(|&(g, f)| groups[g].files[f].size).sum();
+    if !yes && !dry_run {
+        eprintln!(
+            "⚠️  将对 {} 个组中的 {} 个文件（{}）执行 {}",
+            groups.len(),
+            locations.len(),
+            format_size(planned_bytes),
+            action.journal_action().as_str()
+        );
+        return Err(DejaVuError::FileOperationFailed(
+            "Refusing to apply without --yes; preview with --dry-run".to_string(),
+        ));
+    }
+
+    let mut executor = ActionExecutor::new().with_dry_run(dry_run);
+    if let Some(journal) = open_journal() {
+        executor = executor.with_journal(journal);
+    }
+    if let Some(quarantine) = &scan.quarantine {
+        executor = executor.with_quarantine(Quarantine::new(quarantine.clone()));
+    }
+
+    let mut failed = 0;
+    let mut trashed = Vec::new();
+    for &(g, f) in &locations {
+        let path = &groups[g].files[f].path;
+        match executor.execute(&groups[g], f, action) {
+            Ok(outcome) => {
+                warn_unrecorded(&outcome);
+                trashed.extend(outcome.trashed);
+                let mark = if dry_run { "🧪" } else { "✓" };
+                println!("{} {} {}", mark, action.journal_action().as_str(), path.display());
+            }
+            Err(e) => {
+                eprintln!("❌ {}: {}", path.display(), e);
+                failed += 1;
+            }
+        }
+    }
+    record_trash_batch(trashed);
+
+    let summary = executor.summary();
+    if dry_run {
+        print_dry_run_summary(summary);
+    } else {
+        eprintln!(
+            "✅ 已处理 {} 个文件，释放 {}；失败 {} 个",
+            summary.files,
+            format_size(summary.bytes),
+            failed
+        );
+    }
+    Ok(if failed == 0 { EXIT_CLEAN } else { EXIT_ERROR })
+}
+
+/// Steps 1 and 2: collect the media files below `directory` and group duplicates
+fn find_duplicates(scan: &ScanArgs, directory: &Path) -> Result<Vec<DuplicateGroup>> {
     // Check if directory exists
-    if !args.directory.exists() {
+    if !directory.exists() {
         return Err(DejaVuError::PathNotFound(
-            args.directory.display().to_string(),
+            directory.display().to_string(),
         ));
     }
 
     // Step 1: Scan for files
-    println!("🔍 正在扫描目录: {}", args.directory.display());
-    let filter = MediaFilter::new(!args.videos_only, !args.images_only);
-    let collector = FileCollector::new(filter, args.min_size);
+    eprintln!("🔍 正在扫描目录: {}", directory.display());
+    let filter = MediaFilter::new(!scan.videos_only, !scan.images_only);
+    let mut collector = FileCollector::new(filter, scan.min_size);
+    if let Some(quarantine) = &scan.quarantine {
+        collector = collector.with_excluded_dir(quarantine);
+    }
 
     let pb = ProgressBar::new_spinner();
     pb.set_style(
@@ -58,19 +276,19 @@ async fn main() -> Result<()> {
     );
     pb.enable_steady_tick(Duration::from_millis(100));
 
-    let files = collector.collect_with_progress(&args.directory, |found, _total| {
+    let files = collector.collect_with_progress(directory, |found, _total| {
         pb.set_message(format!("已找到 {} 个媒体文件", found));
     })?;
 
     pb.finish_with_message(format!("✓ 扫描完成，共找到 {} 个媒体文件", files.len()));
 
     if files.is_empty() {
-        println!("❌ 指定目录中未找到媒体文件");
-        return Ok(());
+        eprintln!("❌ 指定目录中未找到媒体文件");
+        return Ok(Vec::new());
     }
 
     // Step 2: Find duplicates
-    println!("🔄 正在计算哈希值并查找重复文件...");
+    eprintln!("🔄 正在计算哈希值并查找重复及相似文件...");
     let pb = ProgressBar::new(files.len() as u64);
     pb.set_style(
         ProgressStyle::default_bar()
@@ -79,42 +297,291 @@ async fn main() -> Result<()> {
             .progress_chars("##-"),
     );
 
-    let grouper = HashGrouper::new(args.threshold);
+    let cache = if scan.no_cache {
+        None
+    } else {
+        HashCache::default_path().map(|path| {
+            Arc::new(if scan.rebuild_cache {
+                HashCache::empty(&path)
+            } else {
+                HashCache::load(&path)
+            })
+        })
+    };
+
+    let mut grouper = HashGrouper::new(scan.threshold)
+        .with_exact_hash_algorithm(scan.content_hash)
+        .with_hash_algorithm(scan.hash_algo);
+    if let Some(cache) = &cache {
+        grouper = grouper.with_cache(Arc::clone(cache));
+    }
     let duplicate_groups = grouper.find_duplicates(files, Some(&pb))?;
 
-    pb.finish_with_message(format!("✓ 发现 {} 个重复文件组", duplicate_groups.len()));
+    let exact_count = duplicate_groups.iter().filter(|g| g.is_exact_duplicate()).count();
+    pb.finish_with_message(format!(
+        "✓ 发现 {} 个重复文件组（完全相同 {} 组，相似 {} 组）",
+        duplicate_groups.len(),
+        exact_count,
+        duplicate_groups.len() - exact_count
+    ));
+
+    if let Some(cache) = &cache {
+        let stats = cache.stats();
+        eprintln!(
+            "🗃️  哈希缓存命中: {} / {} ({:.1}%)",
+            stats.hits,
+            stats.lookups(),
+            stats.hit_rate()
+        );
+        if let Err(e) = cache.save() {
+            eprintln!("⚠️  无法保存哈希缓存 {}: {}", cache.path().display(), e);
+        }
+    }
 
     if duplicate_groups.is_empty() {
-        println!("✅ 太棒了！没有发现重复文件");
-        return Ok(());
+        eprintln!("✅ 太棒了！没有发现重复文件");
+    } else {
+        let total_wasted: u64 = duplicate_groups.iter().map(|g| g.wasted_space()).sum();
+        eprintln!("💾 可释放空间: {}", format_size(total_wasted));
+    }
+
+    Ok(duplicate_groups)
+}
+
+/// Run `write` against `output`, or against stdout when no file is given
+fn write_output(output: Option<&Path>, write: impl FnOnce(&mut dyn Write) -> Result<()>) -> Result<()> {
+    match output {
+        Some(path) => {
+            let mut file = io::BufWriter::new(std::fs::File::create(path)?);
+            write(&mut file)?;
+            file.flush()?;
+            eprintln!("📄 已写入: {}", path.display());
+        }
+        None => {
+            let mut stdout = io::stdout().lock();
+            write(&mut stdout)?;
+            stdout.flush()?;
+        }
     }
+    Ok(())
+}
 
-    let total_wasted: u64 = duplicate_groups.iter().map(|g| g.wasted_space()).sum();
-    println!("💾 可释放空间: {}", format_size(total_wasted));
+fn script_report(action: ScriptAction, quarantine: Option<PathBuf>) -> ScriptReport {
+    let script = ScriptReport::new(action);
+    match quarantine {
+        Some(dir) => script.with_quarantine(dir),
+        None => script,
+    }
+}
 
-    // Step 3: Launch TUI
-    println!("\n🚀 正在启动图形界面...");
-    println!("💡 提示: 按 ? 键可查看帮助");
-    run_tui(duplicate_groups)?;
+/// Write a script for the marked files to a new file in the working directory
+///
+/// Uses `mv` into the quarantine directory when one is set, like `d`/`D` do.
+fn export_marked_script(app: &App) -> Result<PathBuf> {
+    let action = if app.quarantine.is_some() { ScriptAction::Mv } else { ScriptAction::Rm };
+    let path = PathBuf::from(format!("dejavu-{}.sh", chrono::Local::now().format("%Y%m%d-%H%M%S")));
 
+    let mut file = io::BufWriter::new(std::fs::File::create_new(&path)?);
+    script_report(action, app.quarantine.clone()).write_selected(&app.duplicate_groups, &app.marked_locations(), &mut file)?;
+    file.flush()?;
+    Ok(path)
+}
+
+/// Final line of a `--dry-run`
+fn print_dry_run_summary(summary: ActionSummary) {
+    eprintln!(
+        "🧪 演练结束：共有 {} 个文件、{} 会受影响，没有改动任何文件",
+        summary.files,
+        format_size(summary.bytes)
+    );
+}
+
+/// `dejavu restore`: undo the most recent trash batch
+fn restore_last_batch(dry_run: bool) -> Result<u8> {
+    if dry_run {
+        preview_restore()?;
+        return Ok(EXIT_CLEAN);
+    }
+
+    let Some((batch, report)) = trash_history()?.undo_last()? else {
+        eprintln!("ℹ️  没有可以恢复的删除记录");
+        return Ok(EXIT_CLEAN);
+    };
+
+    eprintln!("♻️  正在恢复 {} 移至回收站的 {} 个文件", batch.created_at, batch.files.len());
+    print_restore_report(&report);
+    if let Some(journal) = open_journal() {
+        record_restore(&ActionExecutor::new().with_journal(journal), &report);
+    }
+    Ok(if report.is_complete() { EXIT_CLEAN } else { EXIT_ERROR })
+}
+
+/// What an undo would move back, without moving anything
+fn preview_restore() -> Result<()> {
+    let JsonLines { records: mut batches, skipped } = trash_history()?.batches()?;
+    if skipped > 0 {
+        eprintln!("⚠️  回收站历史中有 {} 行无法解析，已跳过", skipped);
+    }
+    match batches.pop() {
+        Some(batch) => {
+            println!("🧪 [演练] 将恢复 {} 移至回收站的 {} 个文件:", batch.created_at, batch.files.len());
+            for file in &batch.files {
+                println!("  {}", file.original_path.display());
+            }
+        }
+        None => println!("ℹ️  没有可以恢复的删除记录"),
+    }
     Ok(())
 }
 
-fn run_tui(duplicate_groups: Vec<DuplicateGroup>) -> Result<()> {
+fn last_trash_batch() -> Result<Option<TrashBatch>> {
+    Ok(trash_history()?.batches()?.records.pop())
+}
+
+fn print_restore_report(report: &RestoreReport) {
+    if report.skipped_lines > 0 {
+        eprintln!("⚠️  回收站历史中有 {} 行无法解析，已丢弃", report.skipped_lines);
+    }
+    for file in &report.restored {
+        println!("✓ 已恢复 {}", file.original_path.display());
+    }
+    for file in &report.conflicts {
+        println!("⚠️  {} 已被其他文件占用，暂不恢复（处理后可再次撤销）", file.original_path.display());
+    }
+    for file in &report.missing {
+        println!("❌ {} 已不在回收站中", file.original_path.display());
+    }
+    for (file, e) in &report.failed {
+        println!("❌ 恢复失败 {}: {}", file.original_path.display(), e);
+    }
+}
+
+/// `dejavu log`: list past sessions, newest first
+fn show_log(verbose: bool, limit: usize) -> Result<u8> {
+    let path = Journal::default_path().ok_or_else(|| {
+        DejaVuError::FileOperationFailed("Cannot determine the data directory for the journal".to_string())
+    })?;
+    let JsonLines { records: entries, skipped } = Journal::read(&path)?;
+    if skipped > 0 {
+        eprintln!("⚠️  操作日志中有 {} 行无法解析，已跳过", skipped);
+    }
+    if entries.is_empty() {
+        eprintln!("ℹ️  操作日志为空: {}", path.display());
+        return Ok(EXIT_CLEAN);
+    }
+
+    eprintln!("📜 操作日志: {}", path.display());
+    for session in Journal::sessions(&entries).iter().rev().take(limit) {
+        let actions: Vec<String> = session
+            .actions
+            .iter()
+            .map(|(action, count)| format!("{} ×{}", action, count))
+            .collect();
+        let actions = if actions.is_empty() {
+            String::new()
+        } else {
+            format!("  ({})", actions.join(", "))
+        };
+        let dry_run = if session.dry_run > 0 {
+            format!(" · 演练 {}", session.dry_run)
+        } else {
+            String::new()
+        };
+        println!(
+            "{}  {} → {}  成功 {} · 失败 {}{} · 释放 {}{}",
+            session.session,
+            session.started_at,
+            session.ended_at,
+            session.succeeded,
+            session.failed,
+            dry_run,
+            format_size(session.freed_bytes),
+            actions
+        );
+
+        if verbose {
+            for entry in entries.iter().filter(|e| e.session == session.session) {
+                match entry.outcome {
+                    Outcome::Ok => println!(
+                        "    ✓ {} {} ({})",
+                        entry.action.as_str(),
+                        entry.path.display(),
+                        format_size(entry.freed_bytes)
+                    ),
+                    Outcome::Failed => println!(
+                        "    ✗ {} {}: {}",
+                        entry.action.as_str(),
+                        entry.path.display(),
+                        entry.error.as_deref().unwrap_or("")
+                    ),
+                    Outcome::DryRun => println!(
+                        "    ◌ {} {} ({}，演练)",
+                        entry.action.as_str(),
+                        entry.path.display(),
+                        format_size(entry.freed_bytes)
+                    ),
+                }
+            }
+        }
+    }
+    Ok(EXIT_CLEAN)
+}
+
+/// Open the operation journal, or continue without one
+fn open_journal() -> Option<Journal> {
+    let path = Journal::default_path()?;
+    match Journal::open(&path) {
+        Ok(journal) => Some(journal),
+        Err(e) => {
+            eprintln!("⚠️  无法打开操作日志 {}，本次操作将不会被记录: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+fn record_restore(executor: &ActionExecutor, report: &RestoreReport) {
+    if let Err(e) = executor.record_restore(report) {
+        eprintln!("⚠️  无法写入操作日志: {}", e);
+    }
+}
+
+fn trash_history() -> Result<TrashHistory> {
+    TrashHistory::default_path().map(TrashHistory::new).ok_or_else(|| {
+        DejaVuError::FileOperationFailed("Cannot determine the data directory for the trash history".to_string())
+    })
+}
+
+/// Remember a batch of trashed files so that it can be undone
+fn record_trash_batch(files: Vec<TrashedFile>) {
+    match trash_history().and_then(|history| history.record(files)) {
+        Ok(0) => {}
+        Ok(left_out) => eprintln!("⚠️  {}", not_undoable(left_out)),
+        Err(e) => eprintln!("⚠️  无法记录删除历史，本次删除将无法撤销: {}", e),
+    }
+}
+
+fn not_undoable(left_out: usize) -> String {
+    format!("{} 个文件的路径不是有效的 UTF-8，无法记录到删除历史，将无法撤销", left_out)
+}
+
+fn run_tui(mut app: App, executor: &ActionExecutor) -> Result<()> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
-
-    // Create app
-    let mut app = App::new(duplicate_groups);
+    let mut loader = app
+        .preview_protocol
+        .map(|protocol| PreviewLoader::new(protocol, CellSize::query()));
 
     // Run event loop
-    let res = run_app(&mut terminal, &mut app);
+    let res = run_app(&mut terminal, &mut app, executor, loader.as_mut());
 
     // Restore terminal
+    if app.preview_protocol == Some(GraphicsProtocol::Kitty) {
+        execute!(terminal.backend_mut(), Print(KITTY_DELETE_ALL))?;
+    }
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
@@ -145,14 +612,26 @@ fn run_tui(duplicate_groups: Vec<DuplicateGroup>) -> Result<()> {
     }
 }
 
+#[allow(clippy::collapsible_if)]
 fn run_app<B: Backend>(
     terminal: &mut Terminal<B>,
     app: &mut App,
+    executor: &ActionExecutor,
+    mut loader: Option<&mut PreviewLoader>,
 ) -> std::result::Result<(), Box<dyn std::error::Error + 'static>>
 where
     <B as Backend>::Error: 'static,
 {
+    // Protocol images written to the terminal, per slot
+    let mut on_screen: [Option<(FileId, Rect)>; 2] = [None, None];
+    let mut size = terminal.size()?;
     loop {
+        for preview in loader.as_deref().map(PreviewLoader::try_recv).unwrap_or_default() {
+            app.set_preview(preview);
+        }
+        app.graphic_placed = [None, None];
+        app.preview_wanted = [None, None];
+
         // Draw UI
         terminal.draw(|f| {
             if app.mode == crate::tui::Mode::Help {
@@ -161,6 +640,14 @@ where
                 MainLayout::render(f, app);
             }
         })?;
+        // A resize clears the screen, images included
+        if terminal.size()? != size {
+            size = terminal.size()?;
+            on_screen = [None, None];
+        }
+        if let Some(loader) = loader.as_deref_mut() {
+            sync_preview(app, loader, &mut on_screen)?;
+        }
 
         // Handle input
         if event::poll(Duration::from_millis(100))? {
@@ -173,79 +660,57 @@ where
                             if let Some(file) = group.files.get(app.selected_file) {
                                 // Leave raw mode temporarily to open file
                                 disable_raw_mode()?;
-                                if let Err(e) = FileOpener::open(&file.path) {
-                                    enable_raw_mode()?;
-                                    return Err(Box::new(e) as Box<dyn std::error::Error>);
-                                }
+                                let opened = FileOpener::open(&file.path);
                                 enable_raw_mode()?;
+                                if let Err(e) = opened {
+                                    app.set_status(StatusKind::Error, format!("❌ 无法打开文件: {}", e));
+                                }
                             }
                         }
                     }
-                    KeyAction::DeleteFile => {
-                        if let Some(group) = app.current_group() {
-                            if let Some(file) = group.files.get(app.selected_file) {
-                                // Confirm deletion
-                                disable_raw_mode()?;
-                                println!(
-                                    "\n⚠️  确定要删除文件 '{}' 吗? (y/n)",
-                                    file.filename()
-                                );
-                                println!("💡 此操作不可撤销，请谨慎操作！");
-                                let mut input = String::new();
-                                std::io::stdin().read_line(&mut input)?;
-                                enable_raw_mode()?;
-
-                                if input.trim().to_lowercase() == "y" {
-                                    if let Err(e) = FileDeleter::delete(&file.path) {
-                                        enable_raw_mode()?;
-                                        eprintln!("❌ 删除失败: {}", e);
-                                        enable_raw_mode()?;
-                                        return Err(Box::new(e) as Box<dyn std::error::Error>);
-                                    }
-                                    println!("✓ 文件已删除");
-                                }
+                    KeyAction::Execute(pending) => {
+                        let description = app.describe(pending.operation);
+                        let result = match pending.operation {
+                            Operation::Remove => remove_files(app, executor, &pending.locations),
+                            Operation::Link(kind) => link_files(app, executor, &pending.locations, kind),
+                        };
+                        let message = format!("已将 {} 个文件{}", result.succeeded, description);
+                        report_batch(app, executor, &result, &message);
+                        app.remove_files(&result.removed);
+                        if pending.from_marks {
+                            app.clear_marks();
+                        }
+                    }
+                    KeyAction::ExportScript => {
+                        match export_marked_script(app) {
+                            Ok(path) => {
+                                let text = format!("📝 已将 {} 个标记文件写入脚本 {}（未改动任何文件）", app.marked_count(), path.display());
+                                app.set_status(StatusKind::Success, text);
                             }
+                            Err(e) => app.set_status(StatusKind::Error, format!("❌ 导出脚本失败: {}", e)),
                         }
                     }
-                    KeyAction::DeleteMarked => {
-                        // Delete all marked files
-                        disable_raw_mode()?;
-                        println!("\n⚠️  确定要删除已标记的 {} 个文件吗? (y/n)", app.marked_count());
-                        println!("💡 此操作不可撤销，请谨慎操作！");
-                        let mut input = String::new();
-                        std::io::stdin().read_line(&mut input)?;
-                        enable_raw_mode()?;
-
-                        if input.trim().to_lowercase() == "y" {
-                            // Collect files to delete
-                            let files_to_delete: Vec<_> = app
-                                .marked_files
-                                .iter()
-                                .filter_map(|&idx| {
-                                    let mut count = 0;
-                                    for group in &app.duplicate_groups {
-                                        for file in &group.files {
-                                            if count == idx {
-                                                return Some(file.path.clone());
-                                            }
-                                            count += 1;
-                                        }
-                                    }
-                                    None
-                                })
-                                .collect();
-
-                            let mut deleted_count = 0;
-                            for path in &files_to_delete {
-                                if let Err(e) = FileDeleter::delete(path) {
-                                    eprintln!("❌ 删除失败 {}: {}", path.display(), e);
-                                } else {
-                                    deleted_count += 1;
+                    KeyAction::Undo => {
+                        let status = if executor.is_dry_run() {
+                            last_trash_batch().map(|batch| match batch {
+                                Some(batch) => (
+                                    StatusKind::Info,
+                                    format!("🧪 [演练] 将恢复 {} 移至回收站的 {} 个文件", batch.created_at, batch.files.len()),
+                                ),
+                                None => (StatusKind::Info, "ℹ️  没有可以撤销的删除".to_string()),
+                            })
+                        } else {
+                            trash_history().and_then(|history| history.undo_last()).map(|undone| match undone {
+                                Some((_, report)) => {
+                                    record_restore(executor, &report);
+                                    restore_status(&report)
                                 }
-                            }
-
-                            println!("✓ 成功删除 {} 个文件", deleted_count);
-                            app.clear_marks();
+                                None => (StatusKind::Info, "ℹ️  没有可以撤销的删除".to_string()),
+                            })
+                        };
+                        match status {
+                            Ok((kind, text)) => app.set_status(kind, text),
+                            Err(e) => app.set_status(StatusKind::Error, format!("❌ 撤销失败: {}", e)),
                         }
                     }
                     KeyAction::None => {}
@@ -258,3 +723,167 @@ where
         }
     }
 }
+
+/// Ask for the previews the last frame needed, and bring the protocol images
+/// on screen in line with the frame
+///
+/// Images are written outside ratatui's buffer, which would count an escape
+/// sequence as that many columns of text and redraw everything after it on
+/// every frame. An image is only written again when it moved or changed.
+/// Kitty images are deleted once no frame shows them; the other protocols are
+/// drawn over like text.
+fn sync_preview(
+    app: &App,
+    loader: &mut PreviewLoader,
+    on_screen: &mut [Option<(FileId, Rect)>; 2],
+) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    for slot in PreviewSlot::ALL {
+        let i = slot.index();
+        if let Some((id, area)) = app.preview_wanted[i]
+            && let Some(file) = app.current_group().and_then(|g| g.files.iter().find(|f| f.id == id))
+        {
+            loader.request(slot, file, area.width, area.height);
+        }
+
+        let placed = app.graphic_placed[i];
+        if placed == on_screen[i] {
+            continue;
+        }
+        if let Some((_, area)) = placed
+            && let Some(Preview { image: PreviewImage::Graphic { data, .. }, .. }) = &app.previews[i]
+        {
+            // Blank the cells first, so that text from earlier frames does
+            // not show around the picture
+            for y in area.top()..area.bottom() {
+                queue!(stdout, MoveTo(area.x, y), Print(" ".repeat(area.width as usize)))?;
+            }
+            queue!(stdout, MoveTo(area.x, area.y), Print(data))?;
+        } else if loader.protocol() == GraphicsProtocol::Kitty {
+            queue!(stdout, Print(kitty_delete(slot)))?;
+        }
+        on_screen[i] = placed;
+    }
+    stdout.flush()
+}
+
+/// Files acted on by one confirmed TUI action
+#[derive(Default)]
+struct BatchResult {
+    succeeded: usize,
+    /// Files that no longer exist at their scanned path, or would not after a
+    /// dry run
+    removed: HashSet<FileId>,
+    errors: Vec<String>,
+    /// Problems that did not stop the action
+    warnings: Vec<String>,
+}
+
+/// Move the files at (group index, file index) `locations` to the quarantine
+/// directory if one was given, or to the trash
+///
+/// Verification failures are reported, not fatal.
+fn remove_files(app: &App, executor: &ActionExecutor, locations: &[(usize, usize)]) -> BatchResult {
+    let action = if app.quarantine.is_some() {
+        FileAction::Quarantine
+    } else {
+        FileAction::Trash
+    };
+    let mut result = BatchResult::default();
+    let mut trashed = Vec::new();
+
+    for &(group_idx, file_idx) in locations {
+        let group = &app.duplicate_groups[group_idx];
+        match executor.execute(group, file_idx, action) {
+            Ok(outcome) => {
+                result.warnings.extend(unrecorded(&outcome));
+                trashed.extend(outcome.trashed);
+                // A dry run drops the file from view too, as the real run would
+                result.removed.insert(group.files[file_idx].id);
+                result.succeeded += 1;
+            }
+            Err(e) => result.errors.push(format!("{}: {}", group.files[file_idx].filename(), e)),
+        }
+    }
+
+    match trash_history().and_then(|history| history.record(trashed)) {
+        Ok(0) => {}
+        Ok(left_out) => result.warnings.push(not_undoable(left_out)),
+        Err(e) => result.warnings.push(format!("无法记录删除历史，本次删除将无法撤销: {}", e)),
+    }
+    result
+}
+
+/// Replace the files at (group index, file index) `locations` with links,
+/// recording the reclaimed space per group unless this is a dry run
+fn link_files(app: &mut App, executor: &ActionExecutor, locations: &[(usize, usize)], kind: LinkKind) -> BatchResult {
+    let mut result = BatchResult::default();
+    for &(group_idx, file_idx) in locations {
+        let group = &app.duplicate_groups[group_idx];
+        match executor.execute(group, file_idx, FileAction::Link(kind)) {
+            Ok(outcome) => {
+                result.warnings.extend(unrecorded(&outcome));
+                if !executor.is_dry_run() {
+                    app.record_reclaimed(group_idx, outcome.freed_bytes);
+                }
+                result.succeeded += 1;
+            }
+            Err(e) => result.errors.push(format!("{}: {}", group.files[file_idx].filename(), e)),
+        }
+    }
+    result
+}
+
+/// Show the outcome of a TUI action in the status bar
+fn report_batch(app: &mut App, executor: &ActionExecutor, result: &BatchResult, message: &str) {
+    if let Some(error) = result.errors.first() {
+        let text = format!(
+            "❌ 成功 {} 个，失败 {} 个 · {}",
+            result.succeeded,
+            result.errors.len(),
+            error
+        );
+        app.set_status(StatusKind::Error, text);
+    } else if let Some(warning) = result.warnings.first() {
+        app.set_status(StatusKind::Warning, format!("⚠️  {}，但{}", message, warning));
+    } else if executor.is_dry_run() {
+        app.set_status(StatusKind::Info, format!("🧪 [演练] {}（未实际执行）", message));
+    } else {
+        app.set_status(StatusKind::Success, format!("✓ {}", message));
+    }
+}
+
+/// Status bar summary of an undo
+fn restore_status(report: &RestoreReport) -> (StatusKind, String) {
+    let mut text = format!("♻️  已恢复 {} 个文件", report.restored.len());
+    if !report.conflicts.is_empty() {
+        text.push_str(&format!("，{} 个原路径已被占用（处理后可再次撤销）", report.conflicts.len()));
+    }
+    if !report.missing.is_empty() {
+        text.push_str(&format!("，{} 个已不在回收站中", report.missing.len()));
+    }
+    if let Some((file, e)) = report.failed.first() {
+        text.push_str(&format!("，{} 个恢复失败 · {}: {}", report.failed.len(), file.original_path.display(), e));
+    }
+    if report.skipped_lines > 0 {
+        text.push_str(&format!("，回收站历史中 {} 行无法解析已丢弃", report.skipped_lines));
+    }
+    let kind = if report.is_complete() && report.skipped_lines == 0 {
+        StatusKind::Success
+    } else {
+        StatusKind::Warning
+    };
+    (kind, text)
+}
+
+fn unrecorded(outcome: &ActionOutcome) -> Vec<String> {
+    let journal = outcome.journal_error.as_ref().map(|e| format!("无法写入操作日志: {}", e));
+    let manifest = outcome.manifest_error.as_ref().map(|e| format!("无法写入隔离清单: {}", e));
+    journal.into_iter().chain(manifest).collect()
+}
+
+fn warn_unrecorded(outcome: &ActionOutcome) {
+    for warning in unrecorded(outcome) {
+        eprintln!("⚠️  操作已完成，但{}", warning);
+    }
+}
@@ -1,11 +1,43 @@
-use crate::models::file_info::FileInfo;
+use crate::hashing::{ExactHashAlgorithm, HashAlgorithm};
+use crate::models::file_info::{FileId, FileInfo};
+
+/// How the files of a group were matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupKind {
+    /// Byte-identical content (same exact hash)
+    Exact,
+    /// Visually similar images (perceptual hashes within the threshold)
+    Similar,
+}
+
+impl GroupKind {
+    /// Stable identifier used in reports
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GroupKind::Exact => "exact",
+            GroupKind::Similar => "similar",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GroupKind::Exact => "完全相同",
+            GroupKind::Similar => "相似",
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct DuplicateGroup {
     pub group_id: usize,
+    pub kind: GroupKind,
     pub files: Vec<FileInfo>,
     pub exact_hash: Option<Vec<u8>>,
+    /// Algorithm that produced `exact_hash`
+    pub exact_algorithm: Option<ExactHashAlgorithm>,
     pub perceptual_hash: Option<u64>,
+    /// Algorithm that produced `perceptual_hash`
+    pub perceptual_algorithm: Option<HashAlgorithm>,
     pub recommended_original: usize, // index in files
 }
 
@@ -15,20 +47,30 @@ impl DuplicateGroup {
 
         Self {
             group_id,
+            kind: GroupKind::Exact,
             files,
             exact_hash: None,
+            exact_algorithm: None,
             perceptual_hash: None,
+            perceptual_algorithm: None,
             recommended_original,
         }
     }
 
-    pub fn with_exact_hash(mut self, hash: Vec<u8>) -> Self {
+    pub fn with_exact_hash(mut self, hash: Vec<u8>, algorithm: ExactHashAlgorithm) -> Self {
         self.exact_hash = Some(hash);
+        self.exact_algorithm = Some(algorithm);
         self
     }
 
-    pub fn with_perceptual_hash(mut self, hash: u64) -> Self {
+    pub fn with_perceptual_hash(mut self, hash: u64, algorithm: HashAlgorithm) -> Self {
         self.perceptual_hash = Some(hash);
+        self.perceptual_algorithm = Some(algorithm);
+        self
+    }
+
+    pub fn with_kind(mut self, kind: GroupKind) -> Self {
+        self.kind = kind;
         self
     }
 
@@ -50,6 +92,31 @@ impl DuplicateGroup {
             .unwrap_or(0)
     }
 
+    /// Keep only the files for which `keep` returns true
+    ///
+    /// The recommended original stays the same file if it is kept; otherwise
+    /// it is chosen again among the remaining files.
+    pub fn retain_files(&mut self, mut keep: impl FnMut(&FileInfo) -> bool) {
+        let original: Option<FileId> = self.files.get(self.recommended_original).map(|f| f.id);
+        self.files.retain(|f| keep(f));
+        self.recommended_original = original
+            .and_then(|id| self.files.iter().position(|f| f.id == id))
+            .unwrap_or_else(|| Self::select_original(&self.files));
+    }
+
+    /// File to compare against before removing `files[index]`: the recommended
+    /// original, or another member of the group when `index` is the original itself
+    pub fn reference_for(&self, index: usize) -> Option<&FileInfo> {
+        if index != self.recommended_original {
+            return self.files.get(self.recommended_original);
+        }
+        self.files
+            .iter()
+            .enumerate()
+            .find(|(i, _)| *i != index)
+            .map(|(_, f)| f)
+    }
+
     pub fn total_size(&self) -> u64 {
         self.files.iter().map(|f| f.size).sum()
     }
@@ -67,6 +134,22 @@ impl DuplicateGroup {
     }
 
     pub fn is_exact_duplicate(&self) -> bool {
-        self.exact_hash.is_some()
+        self.kind == GroupKind::Exact
+    }
+
+    /// Hex representation of the hash shared by this group's files
+    pub fn hash_string(&self) -> Option<String> {
+        match self.kind {
+            GroupKind::Exact => self.exact_hash.as_ref().map(hex::encode),
+            GroupKind::Similar => self.perceptual_hash.map(|h| format!("{:016x}", h)),
+        }
+    }
+
+    /// Name of the algorithm that matched this group's files
+    pub fn algorithm_name(&self) -> Option<&'static str> {
+        match self.kind {
+            GroupKind::Exact => self.exact_algorithm.map(|a| a.name()),
+            GroupKind::Similar => self.perceptual_algorithm.map(|a| a.name()),
+        }
     }
 }
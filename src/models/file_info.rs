@@ -14,6 +14,33 @@ pub enum MediaType {
     Video(VideoFormat),
 }
 
+impl MediaType {
+    /// "image" or "video"
+    pub fn category(&self) -> &'static str {
+        match self {
+            MediaType::Image(_) => "image",
+            MediaType::Video(_) => "video",
+        }
+    }
+
+    /// Lowercase format name, e.g. "jpeg" or "mp4"
+    pub fn format_name(&self) -> &'static str {
+        match self {
+            MediaType::Image(ImageFormat::Jpeg) => "jpeg",
+            MediaType::Image(ImageFormat::Png) => "png",
+            MediaType::Image(ImageFormat::Gif) => "gif",
+            MediaType::Image(ImageFormat::Webp) => "webp",
+            MediaType::Image(ImageFormat::Bmp) => "bmp",
+            MediaType::Image(ImageFormat::Tiff) => "tiff",
+            MediaType::Video(VideoFormat::Mp4) => "mp4",
+            MediaType::Video(VideoFormat::Mov) => "mov",
+            MediaType::Video(VideoFormat::Avi) => "avi",
+            MediaType::Video(VideoFormat::Mkv) => "mkv",
+            MediaType::Video(VideoFormat::Webm) => "webm",
+        }
+    }
+}
+
 /// Supported image file formats
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ImageFormat {
@@ -35,12 +62,22 @@ pub enum VideoFormat {
     Webm,
 }
 
+/// Identifies a file for the length of one scan
+///
+/// Ids are assigned by `HashGrouper::find_duplicates`. A file that appears in
+/// more than one group keeps the same id in each, so the id stays valid while
+/// files and groups are removed around it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileId(pub usize);
+
 /// Information about a media file
 ///
 /// This struct stores metadata about a media file including its path, size,
 /// modification time, and type-specific information like dimensions or duration.
 #[derive(Debug, Clone)]
 pub struct FileInfo {
+    /// Scan-wide identity; `FileId(0)` until the grouper numbers the files
+    pub id: FileId,
     /// Full path to the file
     pub path: PathBuf,
     /// File size in bytes
@@ -52,6 +89,7 @@ pub struct FileInfo {
     /// Image dimensions (width, height) if applicable
     pub dimensions: Option<(u32, u32)>,
     /// Video duration in seconds (for videos only)
+    #[allow(dead_code)]
     pub duration: Option<u64>,
 }
 
@@ -70,6 +108,7 @@ impl FileInfo {
         file_type: MediaType,
     ) -> Self {
         Self {
+            id: FileId::default(),
             path,
             size,
             modified,
@@ -92,6 +131,7 @@ impl FileInfo {
     /// Get the file extension
     ///
     /// Returns an empty string if the file has no extension.
+    #[allow(dead_code)]
     pub fn extension(&self) -> &str {
         self.path
             .extension()
@@ -105,6 +145,7 @@ impl FileInfo {
     }
 
     /// Check if this file is a video
+    #[allow(dead_code)]
     pub fn is_video(&self) -> bool {
         matches!(self.file_type, MediaType::Video(_))
     }
@@ -115,3 +156,28 @@ impl std::fmt::Display for FileInfo {
         write!(f, "{} ({})", self.filename(), self.size)
     }
 }
+
+#[cfg(test)]
+impl FileInfo {
+    /// A 2048-byte JPEG at `path` modified `modified_secs` after the epoch,
+    /// for tests that never open the file
+    pub fn sample(path: impl Into<PathBuf>, modified_secs: u64) -> Self {
+        Self::new(
+            path.into(),
+            2048,
+            SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(modified_secs),
+            MediaType::Image(ImageFormat::Jpeg),
+        )
+    }
+
+    /// The existing file at `path`, as a scan would have recorded it
+    pub fn scanned(path: &std::path::Path) -> Self {
+        let metadata = std::fs::metadata(path).unwrap();
+        Self::new(
+            path.to_path_buf(),
+            metadata.len(),
+            metadata.modified().unwrap(),
+            MediaType::Image(ImageFormat::Jpeg),
+        )
+    }
+}
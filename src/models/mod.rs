@@ -1,5 +1,6 @@
 pub mod file_info;
 pub mod duplicate_group;
 
-pub use file_info::{FileInfo, MediaType};
-pub use duplicate_group::DuplicateGroup;
+#[allow(unused_imports)]
+pub use file_info::{FileId, FileInfo, MediaType};
+pub use duplicate_group::{DuplicateGroup, GroupKind};
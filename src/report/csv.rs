@@ -0,0 +1,80 @@
+//! CSV report
+//!
+//! One row per file, so the report can be sorted and filtered in a spreadsheet.
+//! Files of the same group share a `group_id`.
+
+use crate::error::{DejaVuError, Result};
+use crate::models::DuplicateGroup;
+use crate::report::format_timestamp;
+use serde::Serialize;
+use std::io::Write;
+
+#[derive(Debug, Serialize)]
+struct CsvRow<'a> {
+    /// 1-based group number, as shown in the TUI
+    group_id: usize,
+    kind: &'static str,
+    /// "original" or "duplicate"
+    role: &'static str,
+    path: String,
+    size: u64,
+    modified: String,
+    algorithm: &'static str,
+    hash: &'a str,
+}
+
+pub struct CsvReport;
+
+impl CsvReport {
+    pub fn write(groups: &[DuplicateGroup], out: &mut dyn Write) -> Result<()> {
+        let mut writer = ::csv::Writer::from_writer(out);
+
+        for group in groups {
+            let hash = group.hash_string().unwrap_or_default();
+            for (index, file) in group.files.iter().enumerate() {
+                writer
+                    .serialize(CsvRow {
+                        group_id: group.group_id + 1,
+                        kind: group.kind.as_str(),
+                        role: if index == group.recommended_original { "original" } else { "duplicate" },
+                        path: file.path.display().to_string(),
+                        size: file.size,
+                        modified: format_timestamp(file.modified),
+                        algorithm: group.algorithm_name().unwrap_or_default(),
+                        hash: &hash,
+                    })
+                    .map_err(csv_error)?;
+            }
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+fn csv_error(e: ::csv::Error) -> DejaVuError {
+    DejaVuError::FileOperationFailed(format!("Failed to write CSV report: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashing::ExactHashAlgorithm;
+    use crate::models::FileInfo;
+
+    #[test]
+    fn test_one_row_per_file() {
+        let group = DuplicateGroup::new(0, vec![FileInfo::sample("/photos/a.png", 100), FileInfo::sample("/photos/b, copy.png", 200)])
+            .with_exact_hash(vec![0x01, 0xff], ExactHashAlgorithm::Xxh3);
+
+        let mut out = Vec::new();
+        CsvReport::write(&[group], &mut out).unwrap();
+        let lines: Vec<_> = std::str::from_utf8(&out).unwrap().lines().collect();
+
+        assert_eq!(lines, vec![
+            "group_id,kind,role,path,size,modified,algorithm,hash",
+            "1,exact,original,/photos/a.png,2048,1970-01-01T00:01:40Z,xxh3-128,01ff",
+            "1,exact,duplicate,\"/photos/b, copy.png\",2048,1970-01-01T00:03:20Z,xxh3-128,01ff",
+        ]);
+    }
+}
@@ -0,0 +1,193 @@
+//! Self-contained HTML report
+//!
+//! Thumbnails are downscaled and embedded as base64 JPEG data URIs, so the
+//! page can be shared as a single file and reviewed in any browser.
+
+use crate::error::Result;
+use crate::models::{DuplicateGroup, FileInfo};
+use crate::report::format_timestamp;
+use crate::utils::format_size;
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use rayon::prelude::*;
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+/// Longest edge of an embedded thumbnail, in pixels
+const THUMBNAIL_SIZE: u32 = 240;
+
+const STYLE: &str = "\
+body { font-family: system-ui, sans-serif; margin: 2em; background: #fafafa; color: #222; }
+h1 { margin-bottom: 0.2em; }
+.meta { color: #666; }
+.group { background: #fff; border: 1px solid #ddd; border-radius: 6px; margin: 1.5em 0; padding: 1em; }
+.group h2 { font-size: 1.1em; margin: 0 0 0.8em; }
+.tag { display: inline-block; padding: 0 0.5em; border-radius: 3px; color: #fff; font-size: 0.85em; }
+.tag.exact { background: #2e7d32; }
+.tag.similar { background: #00838f; }
+.files { display: flex; flex-wrap: wrap; gap: 1em; }
+.file { width: 260px; border: 2px solid #eee; border-radius: 4px; padding: 0.5em; word-break: break-all; }
+.file.original { border-color: #2e7d32; background: #f1f8e9; }
+.thumb { width: 240px; height: 240px; display: flex; align-items: center; justify-content: center; background: #f0f0f0; }
+.thumb img { max-width: 240px; max-height: 240px; }
+.thumb span { color: #999; }
+.badge { color: #2e7d32; font-weight: bold; }
+.path { font-family: monospace; font-size: 0.85em; }
+.details { color: #555; font-size: 0.85em; }
+";
+
+pub struct HtmlReport;
+
+impl HtmlReport {
+    pub fn write(groups: &[DuplicateGroup], root: &Path, out: &mut dyn Write) -> Result<()> {
+        // Decoding dominates, so thumbnails are generated in parallel up front
+        let thumbnails: Vec<Vec<Option<String>>> = groups
+            .par_iter()
+            .map(|group| group.files.par_iter().map(thumbnail).collect())
+            .collect();
+
+        let wasted: u64 = groups.iter().map(|g| g.wasted_space()).sum();
+
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">")?;
+        writeln!(out, "<title>DejaVu 重复文件报告 - {}</title>", escape(&root.display().to_string()))?;
+        writeln!(out, "<style>\n{}</style>\n</head>\n<body>", STYLE)?;
+        writeln!(out, "<h1>DejaVu 重复文件报告</h1>")?;
+        writeln!(
+            out,
+            "<p class=\"meta\">{} · {} 个重复文件组 · 可释放 {} · 生成于 {} · DejaVu {}</p>",
+            escape(&root.display().to_string()),
+            groups.len(),
+            format_size(wasted),
+            format_timestamp(std::time::SystemTime::now()),
+            env!("CARGO_PKG_VERSION")
+        )?;
+
+        for (group, thumbnails) in groups.iter().zip(&thumbnails) {
+            Self::write_group(group, thumbnails, out)?;
+        }
+
+        writeln!(out, "</body>\n</html>")?;
+        Ok(())
+    }
+
+    fn write_group(group: &DuplicateGroup, thumbnails: &[Option<String>], out: &mut dyn Write) -> Result<()> {
+        writeln!(out, "<section class=\"group\">")?;
+        writeln!(
+            out,
+            "<h2>组 #{} <span class=\"tag {}\">{}</span> {} · {} 个文件 · 可释放 {}</h2>",
+            group.group_id + 1,
+            group.kind.as_str(),
+            group.kind.label(),
+            group.algorithm_name().unwrap_or(""),
+            group.file_count(),
+            format_size(group.wasted_space())
+        )?;
+        writeln!(out, "<div class=\"files\">")?;
+
+        for (index, (file, thumbnail)) in group.files.iter().zip(thumbnails).enumerate() {
+            let is_original = index == group.recommended_original;
+            writeln!(out, "<div class=\"file{}\">", if is_original { " original" } else { "" })?;
+
+            match thumbnail {
+                Some(uri) => writeln!(
+                    out,
+                    "<div class=\"thumb\"><img src=\"{}\" alt=\"{}\"></div>",
+                    uri,
+                    escape(file.filename())
+                )?,
+                None => writeln!(
+                    out,
+                    "<div class=\"thumb\"><span>{}</span></div>",
+                    file.file_type.format_name().to_uppercase()
+                )?,
+            }
+
+            if is_original {
+                writeln!(out, "<div class=\"badge\">★ 建议保留</div>")?;
+            }
+            writeln!(out, "<div class=\"path\">{}</div>", escape(&file.path.display().to_string()))?;
+            writeln!(out, "<div class=\"details\">{}</div>", escape(&details(file)))?;
+            writeln!(out, "</div>")?;
+        }
+
+        writeln!(out, "</div>\n</section>")?;
+        Ok(())
+    }
+}
+
+/// Size, dimensions and modification time of a file
+fn details(file: &FileInfo) -> String {
+    let mut parts = vec![format_size(file.size)];
+    if let Some((width, height)) = file.dimensions {
+        parts.push(format!("{}×{}", width, height));
+    }
+    parts.push(format_timestamp(file.modified));
+    parts.join(" · ")
+}
+
+/// Downscaled JPEG thumbnail as a data URI, or `None` for videos and
+/// images that cannot be decoded
+fn thumbnail(file: &FileInfo) -> Option<String> {
+    if !file.is_image() {
+        return None;
+    }
+
+    let img = image::open(&file.path).ok()?;
+    let thumb = image::DynamicImage::ImageRgb8(img.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgb8());
+
+    let mut jpeg = Vec::new();
+    thumb
+        .write_to(&mut Cursor::new(&mut jpeg), image::ImageFormat::Jpeg)
+        .ok()?;
+    Some(format!("data:image/jpeg;base64,{}", STANDARD.encode(jpeg)))
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::file_info::{ImageFormat, MediaType};
+    use image::{Rgb, RgbImage};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_embeds_thumbnails_and_highlights_original() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("<big>.png");
+        RgbImage::from_pixel(1000, 500, Rgb([200, 30, 30])).save(&path).unwrap();
+        let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+
+        let image = FileInfo::new(path.clone(), 1000, modified, MediaType::Image(ImageFormat::Png));
+        let missing = FileInfo::new(dir.path().join("gone.png"), 1000, modified, MediaType::Image(ImageFormat::Png));
+        let group = DuplicateGroup::new(0, vec![image, missing]);
+
+        let thumb = thumbnail(&group.files[0]).unwrap();
+        let data = STANDARD.decode(thumb.trim_start_matches("data:image/jpeg;base64,")).unwrap();
+        let decoded = image::load_from_memory(&data).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (THUMBNAIL_SIZE, THUMBNAIL_SIZE / 2));
+
+        let mut out = Vec::new();
+        HtmlReport::write(&[group], dir.path(), &mut out).unwrap();
+        let html = String::from_utf8(out).unwrap();
+
+        assert_eq!(html.matches("data:image/jpeg;base64,").count(), 1);
+        assert_eq!(html.matches("class=\"file original\"").count(), 1);
+        assert!(html.contains("&lt;big&gt;.png"));
+        assert!(!html.contains("<big>"));
+    }
+}
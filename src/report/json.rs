@@ -0,0 +1,164 @@
+//! JSON report
+//!
+//! The layout is part of DejaVu's scripting interface: fields are only ever
+//! added, and any incompatible change bumps `SCHEMA_VERSION`.
+
+use crate::error::{DejaVuError, Result};
+use crate::models::{DuplicateGroup, FileInfo};
+use crate::report::format_timestamp;
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+
+/// Version of the JSON report layout
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+pub struct JsonReport {
+    pub schema_version: u32,
+    pub generator: String,
+    pub generated_at: String,
+    pub root: String,
+    pub summary: JsonSummary,
+    pub groups: Vec<JsonGroup>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonSummary {
+    pub group_count: usize,
+    pub exact_group_count: usize,
+    pub similar_group_count: usize,
+    pub file_count: usize,
+    pub duplicate_file_count: usize,
+    pub wasted_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonGroup {
+    /// 1-based group number, as shown in the TUI
+    pub id: usize,
+    /// "exact" or "similar"
+    pub kind: &'static str,
+    pub algorithm: Option<&'static str>,
+    pub hash: Option<String>,
+    pub file_count: usize,
+    pub total_bytes: u64,
+    pub wasted_bytes: u64,
+    /// Index into `files` of the file recommended to keep
+    pub recommended_original: usize,
+    pub files: Vec<JsonFile>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonFile {
+    pub path: String,
+    pub size: u64,
+    /// Modification time, RFC 3339 in UTC
+    pub modified: String,
+    /// "image" or "video"
+    pub media_type: &'static str,
+    pub format: &'static str,
+    /// "original" or "duplicate"
+    pub role: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<[u32; 2]>,
+}
+
+impl JsonReport {
+    pub fn new(groups: &[DuplicateGroup], root: &Path) -> Self {
+        let file_count: usize = groups.iter().map(|g| g.file_count()).sum();
+        let exact_group_count = groups.iter().filter(|g| g.is_exact_duplicate()).count();
+
+        Self {
+            schema_version: SCHEMA_VERSION,
+            generator: format!("DejaVu {}", env!("CARGO_PKG_VERSION")),
+            generated_at: format_timestamp(std::time::SystemTime::now()),
+            root: root.display().to_string(),
+            summary: JsonSummary {
+                group_count: groups.len(),
+                exact_group_count,
+                similar_group_count: groups.len() - exact_group_count,
+                file_count,
+                duplicate_file_count: file_count.saturating_sub(groups.len()),
+                wasted_bytes: groups.iter().map(|g| g.wasted_space()).sum(),
+            },
+            groups: groups.iter().map(JsonGroup::new).collect(),
+        }
+    }
+
+    pub fn write(&self, out: &mut dyn Write) -> Result<()> {
+        serde_json::to_writer_pretty(&mut *out, self)
+            .map_err(|e| DejaVuError::FileOperationFailed(format!("Failed to write JSON report: {}", e)))?;
+        writeln!(out)?;
+        Ok(())
+    }
+}
+
+impl JsonGroup {
+    fn new(group: &DuplicateGroup) -> Self {
+        Self {
+            id: group.group_id + 1,
+            kind: group.kind.as_str(),
+            algorithm: group.algorithm_name(),
+            hash: group.hash_string(),
+            file_count: group.file_count(),
+            total_bytes: group.total_size(),
+            wasted_bytes: group.wasted_space(),
+            recommended_original: group.recommended_original,
+            files: group
+                .files
+                .iter()
+                .enumerate()
+                .map(|(i, f)| JsonFile::new(f, i == group.recommended_original))
+                .collect(),
+        }
+    }
+}
+
+impl JsonFile {
+    fn new(file: &FileInfo, is_original: bool) -> Self {
+        Self {
+            path: file.path.display().to_string(),
+            size: file.size,
+            modified: format_timestamp(file.modified),
+            media_type: file.file_type.category(),
+            format: file.file_type.format_name(),
+            role: if is_original { "original" } else { "duplicate" },
+            dimensions: file.dimensions.map(|(w, h)| [w, h]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashing::ExactHashAlgorithm;
+
+    #[test]
+    fn test_report_layout() {
+        let mut group = DuplicateGroup::new(0, vec![FileInfo::sample("/photos/copy.jpg", 200), FileInfo::sample("/photos/a.jpg", 100)])
+            .with_exact_hash(vec![0xab, 0xcd], ExactHashAlgorithm::Blake3);
+        group.files[1].dimensions = Some((4000, 3000));
+
+        let mut out = Vec::new();
+        JsonReport::new(&[group], Path::new("/photos")).write(&mut out).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+
+        assert_eq!(value["schema_version"], SCHEMA_VERSION);
+        assert_eq!(value["summary"]["group_count"], 1);
+        assert_eq!(value["summary"]["wasted_bytes"], 2048);
+
+        let group = &value["groups"][0];
+        assert_eq!(group["id"], 1);
+        assert_eq!(group["kind"], "exact");
+        assert_eq!(group["algorithm"], "BLAKE3");
+        assert_eq!(group["hash"], "abcd");
+        assert_eq!(group["recommended_original"], 1);
+        assert_eq!(group["files"][0]["role"], "duplicate");
+        assert_eq!(group["files"][1]["role"], "original");
+        assert_eq!(group["files"][1]["modified"], "1970-01-01T00:01:40Z");
+        assert_eq!(group["files"][1]["format"], "jpeg");
+        assert_eq!(group["files"][1]["dimensions"], serde_json::json!([4000, 3000]));
+        assert!(group["files"][0]["dimensions"].is_null());
+    }
+}
@@ -0,0 +1,59 @@
+//! Non-interactive duplicate reports
+//!
+//! Reports are generated from the same `DuplicateGroup` list the TUI shows.
+
+pub mod csv;
+pub mod html;
+pub mod json;
+pub mod script;
+pub mod text;
+
+pub use csv::CsvReport;
+pub use html::HtmlReport;
+pub use json::JsonReport;
+pub use script::{ScriptAction, ScriptReport};
+pub use text::TextReport;
+
+use crate::error::Result;
+use crate::models::DuplicateGroup;
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::io::Write;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Output formats for `--report`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    /// Versioned JSON document
+    Json,
+    /// One row per file, for spreadsheets
+    Csv,
+    /// fdupes-style path lists separated by blank lines
+    Text,
+    /// Self-contained review page with embedded thumbnails
+    Html,
+}
+
+pub struct ReportGenerator;
+
+impl ReportGenerator {
+    /// Write a report of `groups` found under `root` in the given format
+    pub fn write(
+        format: ReportFormat,
+        groups: &[DuplicateGroup],
+        root: &Path,
+        out: &mut dyn Write,
+    ) -> Result<()> {
+        match format {
+            ReportFormat::Json => JsonReport::new(groups, root).write(out),
+            ReportFormat::Csv => CsvReport::write(groups, out),
+            ReportFormat::Text => TextReport::write(groups, out),
+            ReportFormat::Html => HtmlReport::write(groups, root, out),
+        }
+    }
+}
+
+/// Format a timestamp as RFC 3339 in UTC, e.g. "2024-05-01T12:00:00Z"
+pub fn format_timestamp(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Secs, true)
+}
@@ -0,0 +1,290 @@
+//! Reviewable shell script
+//!
+//! Instead of touching the filesystem, writes a POSIX `sh` script that would
+//! remove, link or move the selected duplicates. Each group is introduced by a
+//! comment naming the file that is kept; paths are absolute and single-quoted,
+//! so the script can be reviewed, edited and run from anywhere.
+
+use crate::error::Result;
+use crate::file_ops::Quarantine;
+use crate::models::{DuplicateGroup, FileInfo};
+use crate::utils::format_size;
+use chrono::Local;
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Command the script runs for each selected duplicate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ScriptAction {
+    /// `rm` the duplicate
+    #[default]
+    Rm,
+    /// Replace the duplicate with a hard link (`ln -f`); exact groups only
+    Ln,
+    /// Replace the duplicate with a symlink to the original (`ln -sf`)
+    Symlink,
+    /// `mv` the duplicate into the quarantine directory, mirroring its path
+    Mv,
+}
+
+impl ScriptAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScriptAction::Rm => "rm",
+            ScriptAction::Ln => "ln",
+            ScriptAction::Symlink => "symlink",
+            ScriptAction::Mv => "mv",
+        }
+    }
+}
+
+pub struct ScriptReport {
+    action: ScriptAction,
+    quarantine: Option<Quarantine>,
+}
+
+impl ScriptReport {
+    pub fn new(action: ScriptAction) -> Self {
+        Self {
+            action,
+            quarantine: None,
+        }
+    }
+
+    /// Directory `mv` moves duplicates into
+    pub fn with_quarantine(mut self, dir: PathBuf) -> Self {
+        self.quarantine = Some(Quarantine::new(dir));
+        self
+    }
+
+    /// Script for every file except each group's recommended original
+    pub fn write(&self, groups: &[DuplicateGroup], out: &mut dyn Write) -> Result<()> {
+        let selection: Vec<(usize, usize)> = groups
+            .iter()
+            .enumerate()
+            .flat_map(|(g, group)| {
+                (0..group.file_count())
+                    .filter(move |&f| f != group.recommended_original)
+                    .map(move |f| (g, f))
+            })
+            .collect();
+        self.write_selected(groups, &selection, out)
+    }
+
+    /// Script for the files at (group index, file index) `selection`
+    ///
+    /// The recommended original is kept unless it is selected itself, in which
+    /// case the first unselected file is; groups with every file selected are
+    /// skipped.
+    pub fn write_selected(
+        &self,
+        groups: &[DuplicateGroup],
+        selection: &[(usize, usize)],
+        out: &mut dyn Write,
+    ) -> Result<()> {
+        let mut selected: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for &(g, f) in selection {
+            if groups.get(g).is_some_and(|group| f < group.file_count()) {
+                selected.entry(g).or_default().push(f);
+            }
+        }
+
+        writeln!(out, "#!/bin/sh")?;
+        writeln!(out, "# DejaVu cleanup script, generated {}", Local::now().format("%Y-%m-%d %H:%M:%S"))?;
+        writeln!(out, "# Action: {}. Nothing has been changed yet: review, then run with sh.", self.action.as_str())?;
+        writeln!(out, "set -eu")?;
+
+        let mut created_dirs = HashSet::new();
+        let (mut count, mut bytes) = (0, 0);
+
+        for (&g, indices) in &selected {
+            let group = &groups[g];
+            writeln!(out)?;
+            writeln!(
+                out,
+                "# group {} · {} · {} · {} files",
+                group.group_id + 1,
+                group.kind.as_str(),
+                group.hash_string().as_deref().unwrap_or("-"),
+                group.file_count()
+            )?;
+
+            let Some(kept) = kept_file(group, indices) else {
+                writeln!(out, "# skipped: every file of this group is selected")?;
+                continue;
+            };
+            writeln!(out, "# keep {}", comment(&kept.path)?)?;
+
+            if self.action == ScriptAction::Ln && !group.is_exact_duplicate() {
+                writeln!(out, "# skipped: files are only similar, hard links need identical content")?;
+                continue;
+            }
+
+            for &f in indices {
+                let file = &group.files[f];
+                if std::ptr::eq(file, kept) {
+                    continue;
+                }
+                self.write_command(out, file, kept, &mut created_dirs)?;
+                count += 1;
+                bytes += file.size;
+            }
+        }
+
+        writeln!(out)?;
+        writeln!(out, "# {} files, {}", count, format_size(bytes))?;
+        Ok(())
+    }
+
+    fn write_command(
+        &self,
+        out: &mut dyn Write,
+        file: &FileInfo,
+        kept: &FileInfo,
+        created_dirs: &mut HashSet<PathBuf>,
+    ) -> Result<()> {
+        let path = std::path::absolute(&file.path)?;
+        let original = std::path::absolute(&kept.path)?;
+
+        let mut line = Vec::new();
+        match self.action {
+            ScriptAction::Rm => command(&mut line, "rm --", &[&path]),
+            ScriptAction::Ln => command(&mut line, "ln -f --", &[&original, &path]),
+            ScriptAction::Symlink => command(&mut line, "ln -sf --", &[&original, &path]),
+            ScriptAction::Mv => {
+                let Some(quarantine) = &self.quarantine else {
+                    return Err(crate::error::DejaVuError::FileOperationFailed(
+                        "mv scripts need a quarantine directory".to_string(),
+                    ));
+                };
+                let dir = quarantine.mirrored_dir(&path);
+                let target = dir.join(path.file_name().unwrap_or_default());
+                if created_dirs.insert(dir.clone()) {
+                    command(&mut line, "mkdir -p --", &[&dir]);
+                    line.push(b'\n');
+                }
+                // Never overwrite something already in quarantine
+                command(&mut line, "[ -e", &[&target]);
+                line.extend_from_slice(b" ] || ");
+                command(&mut line, "mv --", &[&path, &target]);
+            }
+        }
+        line.push(b'\n');
+        out.write_all(&line)?;
+        Ok(())
+    }
+}
+
+/// The file kept in place of the `selected` files of `group`
+fn kept_file<'a>(group: &'a DuplicateGroup, selected: &[usize]) -> Option<&'a FileInfo> {
+    let original = group.recommended_original;
+    if !selected.contains(&original) {
+        return group.files.get(original);
+    }
+    group
+        .files
+        .iter()
+        .enumerate()
+        .find(|(i, _)| !selected.contains(i))
+        .map(|(_, f)| f)
+}
+
+/// Append `program` and its single-quoted `args` to `line`
+fn command(line: &mut Vec<u8>, program: &str, args: &[&Path]) {
+    line.extend_from_slice(program.as_bytes());
+    for arg in args {
+        line.push(b' ');
+        quote(line, arg);
+    }
+}
+
+/// POSIX single quoting: only `'` itself needs escaping, as `'\''`
+fn quote(line: &mut Vec<u8>, path: &Path) {
+    line.push(b'\'');
+    for &b in path.as_os_str().as_encoded_bytes() {
+        if b == b'\'' {
+            line.extend_from_slice(b"'\\''");
+        } else {
+            line.push(b);
+        }
+    }
+    line.push(b'\'');
+}
+
+/// Absolute `path` for a comment line, with newlines escaped so it cannot
+/// end the comment
+fn comment(path: &Path) -> Result<String> {
+    Ok(std::path::absolute(path)?.display().to_string().escape_debug().to_string())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::hashing::{ExactHashAlgorithm, HashAlgorithm};
+    use crate::models::GroupKind;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn render(report: &ScriptReport, groups: &[DuplicateGroup]) -> String {
+        let mut out = Vec::new();
+        report.write(groups, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_rm_script_quotes_paths_and_runs() {
+        let dir = TempDir::new().unwrap();
+        let kept = dir.path().join("a.jpg");
+        let tricky = dir.path().join("it's $HOME `x`\nnew line.jpg");
+        fs::write(&kept, b"same bytes").unwrap();
+        fs::write(&tricky, b"same bytes").unwrap();
+
+        let groups = vec![
+            DuplicateGroup::new(0, vec![FileInfo::sample(&kept, 1), FileInfo::sample(&tricky, 2)])
+                .with_exact_hash(vec![0xab; 4], ExactHashAlgorithm::Sha256),
+        ];
+        let script = render(&ScriptReport::new(ScriptAction::Rm), &groups);
+
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains("# group 1 · exact · abababab · 2 files\n"));
+        assert!(script.contains(&format!("# keep {}\n", kept.display())));
+        assert!(script.contains("it'\\''s $HOME"));
+
+        let status = std::process::Command::new("sh").arg("-c").arg(&script).status().unwrap();
+        assert!(status.success());
+        assert!(kept.exists());
+        assert!(!tricky.exists());
+    }
+
+    #[test]
+    fn test_selection_and_link_variants() {
+        let exact = DuplicateGroup::new(
+            0,
+            vec![
+                FileInfo::sample("/p/a.jpg", 1),
+                FileInfo::sample("/p/b.jpg", 2),
+                FileInfo::sample("/p/c.jpg", 3),
+            ],
+        );
+        let similar = DuplicateGroup::new(1, vec![FileInfo::sample("/p/d.jpg", 1), FileInfo::sample("/p/e.jpg", 2)])
+            .with_perceptual_hash(0xff, HashAlgorithm::Perceptual)
+            .with_kind(GroupKind::Similar);
+        let groups = vec![exact, similar];
+
+        // Marking the original keeps the first unmarked file instead
+        let mut out = Vec::new();
+        ScriptReport::new(ScriptAction::Ln).write_selected(&groups, &[(0, 0), (0, 2), (1, 1)], &mut out).unwrap();
+        let script = String::from_utf8(out).unwrap();
+        assert!(script.contains("# keep /p/b.jpg\nln -f -- '/p/b.jpg' '/p/a.jpg'\nln -f -- '/p/b.jpg' '/p/c.jpg'\n"));
+        assert!(script.contains("# skipped: files are only similar"));
+        assert!(script.contains("# 2 files, "));
+
+        let script = render(&ScriptReport::new(ScriptAction::Symlink), &groups);
+        assert!(script.contains("ln -sf -- '/p/d.jpg' '/p/e.jpg'\n"));
+
+        let script = render(&ScriptReport::new(ScriptAction::Mv).with_quarantine(PathBuf::from("/q")), &groups);
+        assert!(script.contains("mkdir -p -- '/q/p'\n[ -e '/q/p/b.jpg' ] || mv -- '/p/b.jpg' '/q/p/b.jpg'\n"));
+        assert_eq!(script.matches("mkdir").count(), 1);
+    }
+}
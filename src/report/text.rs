@@ -0,0 +1,71 @@
+//! Plain-text report
+//!
+//! Modelled on fdupes: one path per line, groups separated by a blank line.
+//! Each group starts with a `#` summary line and lists the recommended
+//! original first, so `grep -v '^#'` yields fdupes-compatible output.
+
+use crate::error::Result;
+use crate::models::DuplicateGroup;
+use crate::utils::format_size;
+use std::io::Write;
+
+pub struct TextReport;
+
+impl TextReport {
+    pub fn write(groups: &[DuplicateGroup], out: &mut dyn Write) -> Result<()> {
+        for (i, group) in groups.iter().enumerate() {
+            if i > 0 {
+                writeln!(out)?;
+            }
+
+            writeln!(
+                out,
+                "# group {} · {} · {} · {} files · {} wasted",
+                group.group_id + 1,
+                group.kind.as_str(),
+                group.algorithm_name().unwrap_or("-"),
+                group.file_count(),
+                format_size(group.wasted_space())
+            )?;
+
+            let original = group.files.get(group.recommended_original);
+            for file in original.into_iter().chain(
+                group
+                    .files
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| *index != group.recommended_original)
+                    .map(|(_, f)| f),
+            ) {
+                writeln!(out, "{}", file.path.display())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashing::HashAlgorithm;
+    use crate::models::{FileInfo, GroupKind};
+
+    #[test]
+    fn test_groups_separated_by_blank_line() {
+        let groups = vec![
+            DuplicateGroup::new(0, vec![FileInfo::sample("/b.png", 200), FileInfo::sample("/a.png", 100)]),
+            DuplicateGroup::new(1, vec![FileInfo::sample("/c.png", 100), FileInfo::sample("/d.png", 200)])
+                .with_kind(GroupKind::Similar)
+                .with_perceptual_hash(7, HashAlgorithm::Difference),
+        ];
+
+        let mut out = Vec::new();
+        TextReport::write(&groups, &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "# group 1 · exact · - · 2 files · 2.0 KB wasted\n/a.png\n/b.png\n\n\
+             # group 2 · similar · dHash · 2 files · 2.0 KB wasted\n/c.png\n/d.png\n"
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use crate::error::{DejaVuError, Result};
 use crate::models::file_info::FileInfo;
 use crate::scanner::media_filter::MediaFilter;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use walkdir::{WalkDir, DirEntry};
 
 /// File collector for scanning directories and collecting media files
@@ -11,15 +11,32 @@ use walkdir::{WalkDir, DirEntry};
 pub struct FileCollector {
     filter: MediaFilter,
     min_size: u64,
+    /// Canonical paths of directories that are not descended into
+    excluded_dirs: Vec<PathBuf>,
 }
 
 impl FileCollector {
     /// Create a new FileCollector with the specified filter and minimum file size
     pub fn new(filter: MediaFilter, min_size: u64) -> Self {
-        Self { filter, min_size }
+        Self {
+            filter,
+            min_size,
+            excluded_dirs: Vec::new(),
+        }
+    }
+
+    /// Skip `dir` and everything below it, e.g. DejaVu's own quarantine directory
+    ///
+    /// Directories that do not exist yet are ignored.
+    pub fn with_excluded_dir(mut self, dir: &Path) -> Self {
+        if let Ok(dir) = dir.canonicalize() {
+            self.excluded_dirs.push(dir);
+        }
+        self
     }
 
     /// Collect all media files from the specified directory without progress reporting
+    #[allow(dead_code)]
     pub fn collect(&self, directory: &Path) -> Result<Vec<FileInfo>> {
         // Create a dummy progress closure that does nothing
         let no_progress = |_found: usize, _total: usize| {};
@@ -39,6 +56,7 @@ impl FileCollector {
     }
 
     /// Internal implementation shared by both collect methods
+    #[allow(clippy::collapsible_if)]
     fn collect_internal<F>(&self, directory: &Path, mut progress: Option<F>) -> Result<Vec<FileInfo>>
     where
         F: FnMut(usize, usize),
@@ -55,6 +73,7 @@ impl FileCollector {
         for entry in WalkDir::new(directory)
             .follow_links(false)
             .into_iter()
+            .filter_entry(|e| !self.is_excluded(e))
             .filter_map(|e| e.ok())
         {
             total_scanned += 1;
@@ -80,6 +99,16 @@ impl FileCollector {
         Ok(files)
     }
 
+    fn is_excluded(&self, entry: &DirEntry) -> bool {
+        !self.excluded_dirs.is_empty()
+            && entry.file_type().is_dir()
+            && entry
+                .path()
+                .canonicalize()
+                .map(|path| self.excluded_dirs.contains(&path))
+                .unwrap_or(false)
+    }
+
     /// Process a single directory entry and return FileInfo if it's a valid media file
     fn process_entry(&self, entry: &DirEntry) -> Option<FileInfo> {
         let path = entry.path();
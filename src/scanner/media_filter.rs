@@ -30,6 +30,7 @@ impl MediaFilter {
     }
 
     /// Create a filter that accepts all media types (images and videos)
+    #[allow(dead_code)]
     pub fn all() -> Self {
         Self {
             images_enabled: true,
@@ -68,6 +69,7 @@ impl MediaFilter {
     /// # Returns
     /// * `Some(MediaType)` if the file has a supported extension
     /// * `None` if the extension is not recognized or not enabled
+    #[allow(clippy::collapsible_if)]
     pub fn get_media_type(&self, path: &Path) -> Option<MediaType> {
         let ext = path.extension()?.to_str()?.to_lowercase();
 
@@ -1,11 +1,58 @@
-use crate::models::DuplicateGroup;
-use std::collections::HashSet;
+use crate::file_ops::{LinkKind, SymlinkTarget};
+use crate::models::{DuplicateGroup, FileId, FileInfo};
+use crate::tui::graphics::{GraphicsProtocol, Preview, PreviewSlot};
+use crate::tui::ui::file_list::ListScroll;
+use ratatui::layout::Rect;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Mode {
     Normal,
     Help,
     Tutorial,  // 新增：引导模式
+    Confirm,   // 确认对话框
+    Compare,   // 并排比较
+}
+
+/// Files shown side by side in `Mode::Compare`, as indices into the current group
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ComparePair {
+    pub left: usize,
+    pub right: usize,
+}
+
+/// What a confirmed action does to its files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    /// Move to the quarantine directory or the trash
+    Remove,
+    Link(LinkKind),
+}
+
+/// An action waiting for confirmation in `Mode::Confirm`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingAction {
+    pub operation: Operation,
+    /// Affected files as (group index, file index)
+    pub locations: Vec<(usize, usize)>,
+    /// Clear the marks once the action ran
+    pub from_marks: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusKind {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+/// Result of the last action, shown in the status bar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusMessage {
+    pub kind: StatusKind,
+    pub text: String,
 }
 
 pub struct App {
@@ -14,7 +61,33 @@ pub struct App {
     pub duplicate_groups: Vec<DuplicateGroup>,
     pub selected_group: usize,
     pub selected_file: usize,
-    pub marked_files: HashSet<usize>,
+    /// Scroll positions of the group and file lists
+    pub group_scroll: ListScroll,
+    pub file_scroll: ListScroll,
+    pub marked_files: HashSet<FileId>,
+    /// Bytes actually reclaimed by linking, per group id
+    pub reclaimed_space: HashMap<usize, u64>,
+    /// How `s`/`S` symlinks refer to the original
+    pub symlink_target: SymlinkTarget,
+    /// Directory `d`/`D` move files to instead of the trash
+    pub quarantine: Option<PathBuf>,
+    /// `--dry-run`: actions are only checked and journaled
+    pub dry_run: bool,
+    /// Action shown in the confirmation dialog
+    pub pending: Option<PendingAction>,
+    pub status: Option<StatusMessage>,
+    /// How previews are drawn; `None` without a preview pane
+    pub preview_protocol: Option<GraphicsProtocol>,
+    /// Toggled with `p`
+    pub show_preview: bool,
+    /// Latest preview per slot, possibly of another file
+    pub previews: [Option<Preview>; 2],
+    /// File and area each preview pane asked to fill in the last frame
+    pub preview_wanted: [Option<(FileId, Rect)>; 2],
+    /// File and cells of each slot's protocol image in the last frame; the
+    /// event loop writes the image there once the frame is drawn
+    pub graphic_placed: [Option<(FileId, Rect)>; 2],
+    pub compare: ComparePair,
     pub show_tutorial: bool,  // 是否显示引导
     pub tutorial_step: usize,  // 引导步骤
 }
@@ -28,16 +101,79 @@ impl App {
             duplicate_groups,
             selected_group: 0,
             selected_file: 0,
+            group_scroll: ListScroll::default(),
+            file_scroll: ListScroll::default(),
             marked_files: HashSet::new(),
+            reclaimed_space: HashMap::new(),
+            symlink_target: SymlinkTarget::default(),
+            quarantine: None,
+            dry_run: false,
+            pending: None,
+            status: None,
+            preview_protocol: None,
+            show_preview: true,
+            previews: [None, None],
+            preview_wanted: [None, None],
+            graphic_placed: [None, None],
+            compare: ComparePair::default(),
             show_tutorial,
             tutorial_step: 0,
         }
     }
 
+    pub fn with_symlink_target(mut self, target: SymlinkTarget) -> Self {
+        self.symlink_target = target;
+        self
+    }
+
+    pub fn with_quarantine(mut self, quarantine: Option<PathBuf>) -> Self {
+        self.quarantine = quarantine;
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn with_preview(mut self, protocol: Option<GraphicsProtocol>) -> Self {
+        self.preview_protocol = protocol;
+        self
+    }
+
+    /// Whether the preview pane is shown
+    pub fn preview_visible(&self) -> bool {
+        self.preview_protocol.is_some() && self.show_preview
+    }
+
+    pub fn toggle_preview(&mut self) {
+        if self.preview_protocol.is_some() {
+            self.show_preview = !self.show_preview;
+        }
+    }
+
+    /// Keep a finished preview for its slot
+    pub fn set_preview(&mut self, preview: Preview) {
+        let slot = preview.slot.index();
+        self.previews[slot] = Some(preview);
+    }
+
+    /// The preview of `file` in `slot` if it was made for an area of this size
+    pub fn preview_for(&self, slot: PreviewSlot, file: FileId, area: Rect) -> Option<&Preview> {
+        self.previews[slot.index()]
+            .as_ref()
+            .filter(|p| p.fits(file, area.width, area.height))
+    }
+
     pub fn current_group(&self) -> Option<&DuplicateGroup> {
         self.duplicate_groups.get(self.selected_group)
     }
 
+    pub fn current_file(&self) -> Option<&FileInfo> {
+        self.current_group()
+            .and_then(|g| g.files.get(self.selected_file))
+    }
+
     pub fn group_count(&self) -> usize {
         self.duplicate_groups.len()
     }
@@ -62,6 +198,24 @@ impl App {
         }
     }
 
+    /// Select the group at `index`, clamped to the list
+    pub fn select_group(&mut self, index: usize) {
+        let index = index.min(self.group_count().saturating_sub(1));
+        if index != self.selected_group {
+            self.selected_group = index;
+            self.selected_file = 0; // 重置文件选择
+        }
+    }
+
+    pub fn next_group_page(&mut self) {
+        self.select_group(self.selected_group + self.group_scroll.page());
+    }
+
+    pub fn previous_group_page(&mut self) {
+        self.select_group(self.selected_group.saturating_sub(self.group_scroll.page()));
+    }
+
+    #[allow(clippy::collapsible_if)]
     pub fn next_file(&mut self) {
         if let Some(group) = self.current_group() {
             if group.file_count() > 0 {
@@ -71,6 +225,7 @@ impl App {
         }
     }
 
+    #[allow(clippy::collapsible_if)]
     pub fn previous_file(&mut self) {
         if let Some(group) = self.current_group() {
             if group.file_count() > 0 {
@@ -83,24 +238,95 @@ impl App {
         }
     }
 
+    /// Compare the recommended original with the selected file, or with the
+    /// next one when the original is selected
+    pub fn enter_compare(&mut self) {
+        let Some(group) = self.current_group() else {
+            return;
+        };
+        let count = group.file_count();
+        if count < 2 {
+            return;
+        }
+        let left = group.recommended_original;
+        let right = if self.selected_file != left { self.selected_file } else { (left + 1) % count };
+        self.compare = ComparePair { left, right };
+        self.load_dimensions(left);
+        self.load_dimensions(right);
+        self.mode = Mode::Compare;
+    }
+
+    /// Back to the file list, with the right-hand file selected
+    pub fn exit_compare(&mut self) {
+        self.selected_file = self.compare.right;
+        self.mode = Mode::Normal;
+    }
+
+    /// Show the next file of the group on the right, skipping the left one
+    pub fn next_compare_file(&mut self) {
+        self.cycle_compare(1);
+    }
+
+    pub fn previous_compare_file(&mut self) {
+        self.cycle_compare(self.file_count().saturating_sub(1));
+    }
+
+    fn cycle_compare(&mut self, step: usize) {
+        let count = self.file_count();
+        if count < 2 {
+            return;
+        }
+        let mut right = (self.compare.right + step) % count;
+        if right == self.compare.left {
+            right = (right + step) % count;
+        }
+        self.compare.right = right;
+        self.load_dimensions(right);
+    }
+
+    /// Make the file at `index` of the current group the one to keep; it is
+    /// unmarked so that `D` does not remove it
+    pub fn keep_file(&mut self, index: usize) {
+        let Some(group) = self.duplicate_groups.get_mut(self.selected_group) else {
+            return;
+        };
+        let Some(file) = group.files.get(index) else {
+            return;
+        };
+        let (id, name) = (file.id, file.filename().to_string());
+        group.recommended_original = index;
+        self.marked_files.remove(&id);
+        self.set_status(StatusKind::Success, format!("✓ 已将 {} 设为保留的文件", name));
+    }
+
+    /// Read the pixel size of an image from its header, once
+    fn load_dimensions(&mut self, index: usize) {
+        if let Some(file) = self
+            .duplicate_groups
+            .get_mut(self.selected_group)
+            .and_then(|g| g.files.get_mut(index))
+            && file.is_image()
+            && file.dimensions.is_none()
+        {
+            file.dimensions = image::image_dimensions(&file.path).ok();
+        }
+    }
+
     pub fn toggle_mark(&mut self) {
-        if self.current_group().is_some() {
-            let global_index = self.get_global_file_index(self.selected_group, self.selected_file);
-            if self.marked_files.contains(&global_index) {
-                self.marked_files.remove(&global_index);
-            } else {
-                self.marked_files.insert(global_index);
-            }
+        if let Some(id) = self.current_file().map(|f| f.id)
+            && !self.marked_files.remove(&id)
+        {
+            self.marked_files.insert(id);
         }
     }
 
+    pub fn is_marked(&self, id: FileId) -> bool {
+        self.marked_files.contains(&id)
+    }
+
     pub fn is_current_file_marked(&self) -> bool {
-        if self.current_group().is_some() {
-            let global_index = self.get_global_file_index(self.selected_group, self.selected_file);
-            self.marked_files.contains(&global_index)
-        } else {
-            false
-        }
+        self.current_file()
+            .is_some_and(|f| self.is_marked(f.id))
     }
 
     pub fn marked_count(&self) -> usize {
@@ -108,17 +334,133 @@ impl App {
     }
 
     pub fn marked_count_in_group(&self, group_idx: usize) -> usize {
-        let mut count = 0;
-        let mut global_idx = 0;
-        for (i, group) in self.duplicate_groups.iter().enumerate() {
-            for _ in &group.files {
-                if i == group_idx && self.marked_files.contains(&global_idx) {
-                    count += 1;
+        self.duplicate_groups
+            .get(group_idx)
+            .map(|g| g.files.iter().filter(|f| self.is_marked(f.id)).count())
+            .unwrap_or(0)
+    }
+
+    /// Marked files as (group index, file index), in display order
+    ///
+    /// A file shown in several groups is listed once, at its first location.
+    pub fn marked_locations(&self) -> Vec<(usize, usize)> {
+        let mut seen = HashSet::new();
+        let mut locations = Vec::new();
+        for (group_idx, group) in self.duplicate_groups.iter().enumerate() {
+            for (file_idx, file) in group.files.iter().enumerate() {
+                if self.is_marked(file.id) && seen.insert(file.id) {
+                    locations.push((group_idx, file_idx));
                 }
-                global_idx += 1;
             }
         }
-        count
+        locations
+    }
+
+    /// Drop files that no longer exist from every group they appear in
+    ///
+    /// Groups left with fewer than two files are dropped too. The selection
+    /// follows the previously selected group and file where they remain, and
+    /// marks on files that are no longer shown are cleared.
+    pub fn remove_files(&mut self, ids: &HashSet<FileId>) {
+        if ids.is_empty() {
+            return;
+        }
+        let selected_group_id = self.current_group().map(|g| g.group_id);
+        let selected_file_id = self.current_file().map(|f| f.id);
+
+        for group in &mut self.duplicate_groups {
+            group.retain_files(|f| !ids.contains(&f.id));
+        }
+        self.duplicate_groups.retain(|g| g.file_count() >= 2);
+
+        let shown: HashSet<FileId> = self
+            .duplicate_groups
+            .iter()
+            .flat_map(|g| g.files.iter().map(|f| f.id))
+            .collect();
+        self.marked_files.retain(|id| shown.contains(id));
+
+        match self.duplicate_groups.iter().position(|g| Some(g.group_id) == selected_group_id) {
+            Some(group_idx) => {
+                let files = &self.duplicate_groups[group_idx].files;
+                self.selected_file = files
+                    .iter()
+                    .position(|f| Some(f.id) == selected_file_id)
+                    .unwrap_or_else(|| self.selected_file.min(files.len() - 1));
+                self.selected_group = group_idx;
+            }
+            None => {
+                self.selected_group = self.selected_group.min(self.group_count().saturating_sub(1));
+                self.selected_file = 0;
+            }
+        }
+    }
+
+    pub fn record_reclaimed(&mut self, group_idx: usize, bytes: u64) {
+        if let Some(group_id) = self.duplicate_groups.get(group_idx).map(|g| g.group_id) {
+            *self.reclaimed_space.entry(group_id).or_default() += bytes;
+        }
+    }
+
+    pub fn reclaimed_in_group(&self, group_idx: usize) -> u64 {
+        self.duplicate_groups
+            .get(group_idx)
+            .and_then(|g| self.reclaimed_space.get(&g.group_id))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub fn total_reclaimed(&self) -> u64 {
+        self.reclaimed_space.values().sum()
+    }
+
+    /// Ask for confirmation before running `operation` on `locations`
+    pub fn request_confirm(&mut self, operation: Operation, locations: Vec<(usize, usize)>, from_marks: bool) {
+        if locations.is_empty() {
+            return;
+        }
+        self.pending = Some(PendingAction {
+            operation,
+            locations,
+            from_marks,
+        });
+        self.mode = Mode::Confirm;
+    }
+
+    /// Leave the dialog, returning the action if it was confirmed
+    pub fn close_confirm(&mut self, confirmed: bool) -> Option<PendingAction> {
+        self.mode = Mode::Normal;
+        let pending = self.pending.take();
+        if !confirmed {
+            self.set_status(StatusKind::Info, "已取消");
+            return None;
+        }
+        pending
+    }
+
+    /// What the pending action does, e.g. "移至回收站"
+    pub fn pending_description(&self) -> String {
+        self.pending
+            .as_ref()
+            .map(|p| self.describe(p.operation))
+            .unwrap_or_default()
+    }
+
+    pub fn describe(&self, operation: Operation) -> String {
+        match operation {
+            Operation::Remove => match &self.quarantine {
+                Some(dir) => format!("移至隔离目录 {}", dir.display()),
+                None => "移至回收站".to_string(),
+            },
+            Operation::Link(kind) => format!("替换为指向原始文件的{}", kind.label()),
+        }
+    }
+
+    pub fn set_status(&mut self, kind: StatusKind, text: impl Into<String>) {
+        self.status = Some(StatusMessage {
+            kind,
+            text: text.into(),
+        });
     }
 
     pub fn clear_marks(&mut self) {
@@ -159,17 +501,6 @@ impl App {
         self.should_quit = true;
     }
 
-    fn get_global_file_index(&self, group_idx: usize, file_idx: usize) -> usize {
-        let mut index = 0;
-        for (i, group) in self.duplicate_groups.iter().enumerate() {
-            if i == group_idx {
-                return index + file_idx;
-            }
-            index += group.file_count();
-        }
-        index
-    }
-
     // 获取当前操作提示
     pub fn get_action_hint(&self) -> &'static str {
         if self.marked_count() > 0 {
@@ -181,3 +512,123 @@ impl App {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::file_info::{ImageFormat, MediaType};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn group(group_id: usize, files: &[(usize, &str)]) -> DuplicateGroup {
+        let files = files
+            .iter()
+            .map(|&(id, path)| {
+                let mut file = FileInfo::new(
+                    PathBuf::from(path),
+                    10,
+                    UNIX_EPOCH + Duration::from_secs(id as u64),
+                    MediaType::Image(ImageFormat::Jpeg),
+                );
+                file.id = FileId(id);
+                file
+            })
+            .collect();
+        DuplicateGroup::new(group_id, files)
+    }
+
+    #[test]
+    fn test_marks_follow_files_across_removals() {
+        let mut app = App::new(vec![
+            group(0, &[(0, "/a/1.jpg"), (1, "/a/2.jpg")]),
+            group(1, &[(2, "/b/1.jpg"), (3, "/b/2.jpg"), (4, "/b/3.jpg")]),
+            group(2, &[(3, "/b/2.jpg"), (5, "/c/1.jpg")]),
+        ]);
+        app.selected_group = 1;
+        app.selected_file = 2;
+        app.toggle_mark();
+        app.selected_file = 1;
+        app.toggle_mark();
+        app.record_reclaimed(1, 100);
+
+        // The file shared with group 2 is marked there too, but acted on once
+        assert_eq!(app.marked_count_in_group(2), 1);
+        assert_eq!(app.marked_locations(), vec![(1, 1), (1, 2)]);
+
+        // Deleting a file of group 0 drops the group without shifting marks
+        app.remove_files(&HashSet::from([FileId(1)]));
+        assert_eq!(app.group_count(), 2);
+        assert_eq!(app.marked_locations(), vec![(0, 1), (0, 2)]);
+        assert_eq!((app.selected_group, app.selected_file), (0, 1));
+        assert_eq!(app.reclaimed_in_group(0), 100);
+
+        app.remove_files(&HashSet::from([FileId(3), FileId(4)]));
+        assert_eq!(app.group_count(), 0);
+        assert_eq!(app.marked_count(), 0);
+        assert_eq!(app.total_reclaimed(), 100);
+        assert!(app.current_file().is_none());
+    }
+
+    #[test]
+    fn test_compare_cycles_and_keeps() {
+        let mut app = App::new(vec![group(0, &[(3, "/a/3.jpg"), (1, "/a/1.jpg"), (2, "/a/2.jpg")])]);
+        app.selected_file = 1;
+        app.enter_compare();
+        // The original is selected, so the next file goes on the right
+        assert_eq!(app.mode, Mode::Compare);
+        assert_eq!(app.compare, ComparePair { left: 1, right: 2 });
+
+        app.next_compare_file();
+        assert_eq!(app.compare.right, 0);
+        app.next_compare_file();
+        assert_eq!(app.compare.right, 2);
+        app.previous_compare_file();
+        app.previous_compare_file();
+        assert_eq!(app.compare.right, 2);
+
+        app.toggle_mark();
+        app.selected_file = 2;
+        app.toggle_mark();
+        app.keep_file(app.compare.right);
+        assert_eq!(app.duplicate_groups[0].recommended_original, 2);
+        assert_eq!(app.marked_locations(), vec![(0, 1)]);
+
+        app.exit_compare();
+        assert_eq!((app.mode, app.selected_file), (Mode::Normal, 2));
+    }
+
+    #[test]
+    fn test_paging_clamps_to_group_list() {
+        let groups = (0..25)
+            .map(|i| group(i, &[(2 * i, "/a.jpg"), (2 * i + 1, "/b.jpg")]))
+            .collect();
+        let mut app = App::new(groups);
+        app.group_scroll.window(0, 25, 10);
+
+        app.next_group_page();
+        assert_eq!(app.selected_group, 10);
+        app.selected_file = 1;
+        app.next_group_page();
+        app.next_group_page();
+        assert_eq!((app.selected_group, app.selected_file), (24, 0));
+        app.previous_group_page();
+        assert_eq!(app.selected_group, 14);
+        app.select_group(0);
+        app.previous_group_page();
+        assert_eq!(app.selected_group, 0);
+    }
+
+    #[test]
+    fn test_removal_keeps_recommended_original() {
+        let mut app = App::new(vec![group(0, &[(3, "/a/3.jpg"), (1, "/a/1.jpg"), (2, "/a/2.jpg")])]);
+        assert_eq!(app.duplicate_groups[0].recommended_original, 1);
+
+        app.remove_files(&HashSet::from([FileId(3)]));
+        assert_eq!(app.duplicate_groups[0].recommended_original, 0);
+        assert_eq!(app.duplicate_groups[0].files[0].id, FileId(1));
+
+        // Removing the original itself picks a new one
+        app.selected_file = 1;
+        app.remove_files(&HashSet::from([FileId(1)]));
+        assert_eq!(app.group_count(), 0);
+    }
+}
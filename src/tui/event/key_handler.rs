@@ -1,11 +1,14 @@
 use crossterm::event::{KeyCode, KeyEvent};
-use crate::tui::{App, Mode};
+use crate::file_ops::LinkKind;
+use crate::tui::{App, Mode, Operation, PendingAction};
 
 pub enum KeyAction {
     None,
     OpenFile,
-    DeleteFile,
-    DeleteMarked,
+    /// A confirmed delete or link
+    Execute(PendingAction),
+    ExportScript,
+    Undo,
 }
 
 pub fn handle_key_event(key_event: KeyEvent, app: &mut App) -> KeyAction {
@@ -34,6 +37,27 @@ pub fn handle_key_event(key_event: KeyEvent, app: &mut App) -> KeyAction {
             }
             _ => KeyAction::None,
         }
+    } else if app.mode == Mode::Confirm {
+        match key_event.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                app.close_confirm(true).map_or(KeyAction::None, KeyAction::Execute)
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc | KeyCode::Char('q') => {
+                app.close_confirm(false);
+                KeyAction::None
+            }
+            _ => KeyAction::None,
+        }
+    } else if app.mode == Mode::Compare {
+        match key_event.code {
+            KeyCode::Tab | KeyCode::Right | KeyCode::Char('l') => app.next_compare_file(),
+            KeyCode::BackTab | KeyCode::Left | KeyCode::Char('h') => app.previous_compare_file(),
+            KeyCode::Char('1') => app.keep_file(app.compare.left),
+            KeyCode::Char('2') => app.keep_file(app.compare.right),
+            KeyCode::Esc | KeyCode::Char('c') | KeyCode::Char('q') => app.exit_compare(),
+            _ => {}
+        }
+        KeyAction::None
     } else if app.mode == Mode::Help {
         // 任意键关闭帮助
         app.hide_help();
@@ -92,53 +116,104 @@ pub fn handle_key_event(key_event: KeyEvent, app: &mut App) -> KeyAction {
                 KeyAction::OpenFile
             }
 
+            // 并排比较
+            KeyCode::Char('c') => {
+                app.enter_compare();
+                KeyAction::None
+            }
+
+            // 显示/隐藏预览
+            KeyCode::Char('p') => {
+                app.toggle_preview();
+                KeyAction::None
+            }
+
             // 删除文件
             KeyCode::Char('d') => {
-                KeyAction::DeleteFile
+                request_current(app, Operation::Remove);
+                KeyAction::None
             }
 
             // 删除所有标记
             KeyCode::Char('D') => {
+                request_marked(app, Operation::Remove);
+                KeyAction::None
+            }
+
+            // 用硬链接替换当前文件 / 所有标记
+            KeyCode::Char('l') => {
+                request_current(app, Operation::Link(LinkKind::Hard));
+                KeyAction::None
+            }
+
+            KeyCode::Char('L') => {
+                request_marked(app, Operation::Link(LinkKind::Hard));
+                KeyAction::None
+            }
+
+            // 用 reflink 替换当前文件 / 所有标记
+            KeyCode::Char('r') => {
+                request_current(app, Operation::Link(LinkKind::Reflink));
+                KeyAction::None
+            }
+
+            KeyCode::Char('R') => {
+                request_marked(app, Operation::Link(LinkKind::Reflink));
+                KeyAction::None
+            }
+
+            // 用符号链接替换当前文件 / 所有标记
+            KeyCode::Char('s') => {
+                request_current(app, Operation::Link(LinkKind::Symlink(app.symlink_target)));
+                KeyAction::None
+            }
+
+            KeyCode::Char('S') => {
+                request_marked(app, Operation::Link(LinkKind::Symlink(app.symlink_target)));
+                KeyAction::None
+            }
+
+            // 把标记导出为 shell 脚本
+            KeyCode::Char('x') => {
                 if app.marked_count() > 0 {
-                    KeyAction::DeleteMarked
+                    KeyAction::ExportScript
                 } else {
                     KeyAction::None
                 }
             }
 
+            // 撤销最近一次删除
+            KeyCode::Char('z') => {
+                KeyAction::Undo
+            }
+
             // 清除标记
             KeyCode::Char('u') => {
                 app.clear_marks();
                 KeyAction::None
             }
 
-            // Page Down - 跳转5组
+            // Page Down - 向下翻一页
             KeyCode::PageDown => {
-                for _ in 0..5 {
-                    app.next_group();
-                }
+                app.next_group_page();
                 KeyAction::None
             }
 
-            // Page Up - 回退5组
+            // Page Up - 向上翻一页
             KeyCode::PageUp => {
-                for _ in 0..5 {
-                    app.previous_group();
-                }
+                app.previous_group_page();
                 KeyAction::None
             }
 
             // Home - 第一组
             KeyCode::Home => {
-                app.selected_group = 0;
-                app.selected_file = 0;
+                app.select_group(0);
                 KeyAction::None
             }
 
             // End - 最后一组
             KeyCode::End => {
-                app.selected_group = app.group_count().saturating_sub(1);
-                app.selected_file = 0;
+                app.select_group(usize::MAX);
                 KeyAction::None
             }
 
@@ -154,3 +229,74 @@ pub fn handle_key_event(key_event: KeyEvent, app: &mut App) -> KeyAction {
         }
     }
 }
+
+/// Confirm `operation` on the selected file
+fn request_current(app: &mut App, operation: Operation) {
+    if app.file_count() > 0 {
+        let location = (app.selected_group, app.selected_file);
+        app.request_confirm(operation, vec![location], false);
+    }
+}
+
+/// Confirm `operation` on all marked files
+fn request_marked(app: &mut App, operation: Operation) {
+    let locations = app.marked_locations();
+    app.request_confirm(operation, locations, true);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DuplicateGroup, FileInfo};
+    use crate::models::file_info::{ImageFormat, MediaType};
+    use crossterm::event::KeyModifiers;
+    use std::path::PathBuf;
+    use std::time::UNIX_EPOCH;
+
+    fn press(app: &mut App, code: KeyCode) -> KeyAction {
+        handle_key_event(KeyEvent::new(code, KeyModifiers::NONE), app)
+    }
+
+    fn app() -> App {
+        let files = ["/p/a.jpg", "/p/b.jpg"]
+            .iter()
+            .map(|p| FileInfo::new(PathBuf::from(p), 10, UNIX_EPOCH, MediaType::Image(ImageFormat::Jpeg)))
+            .collect();
+        let mut app = App::new(vec![DuplicateGroup::new(0, files)]);
+        app.exit_tutorial();
+        app
+    }
+
+    #[test]
+    fn test_delete_waits_for_confirmation() {
+        let mut app = app();
+        app.selected_file = 1;
+
+        assert!(matches!(press(&mut app, KeyCode::Char('d')), KeyAction::None));
+        assert_eq!(app.mode, Mode::Confirm);
+        // Other keys do nothing while the dialog is open
+        assert!(matches!(press(&mut app, KeyCode::Char('j')), KeyAction::None));
+        assert_eq!(app.mode, Mode::Confirm);
+
+        match press(&mut app, KeyCode::Char('y')) {
+            KeyAction::Execute(pending) => {
+                assert_eq!(pending.operation, Operation::Remove);
+                assert_eq!(pending.locations, vec![(0, 1)]);
+            }
+            _ => panic!("expected the confirmed action"),
+        }
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn test_escape_cancels() {
+        let mut app = app();
+        app.toggle_mark();
+
+        press(&mut app, KeyCode::Char('L'));
+        assert!(matches!(press(&mut app, KeyCode::Esc), KeyAction::None));
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.pending.is_none());
+        assert_eq!(app.marked_count(), 1);
+    }
+}
@@ -0,0 +1,224 @@
+//! Image encoders for each graphics protocol
+//!
+//! Every encoder takes an already downscaled image; graphic protocols get
+//! one that fits the area in pixels, half blocks one pixel per column and two
+//! per row.
+
+use super::{CellSize, GraphicsProtocol, PreviewImage, PreviewSlot};
+use crate::error::Result;
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use image::{DynamicImage, RgbImage};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::Cursor;
+
+/// Largest payload of one Kitty graphics escape sequence
+const KITTY_CHUNK: usize = 4096;
+
+/// Removes every Kitty image, e.g. when leaving the TUI
+pub const KITTY_DELETE_ALL: &str = "\x1b_Ga=d,d=A,q=2\x1b\\";
+
+/// Removes the Kitty image of `slot`
+pub fn kitty_delete(slot: PreviewSlot) -> String {
+    format!("\x1b_Ga=d,d=I,i={},q=2\x1b\\", slot.kitty_id())
+}
+
+/// Downscale `image` to fit `cols` × `rows` cells and encode it for `protocol`
+/// in the pane of `slot`
+pub fn encode(
+    image: &DynamicImage,
+    protocol: GraphicsProtocol,
+    cols: u16,
+    rows: u16,
+    cell: CellSize,
+    slot: PreviewSlot,
+) -> Result<PreviewImage> {
+    if protocol == GraphicsProtocol::HalfBlocks {
+        let thumb = image.thumbnail(cols as u32, rows as u32 * 2).to_rgb8();
+        return Ok(PreviewImage::Blocks(half_blocks(&thumb)));
+    }
+
+    let thumb = image.thumbnail(cols as u32 * cell.width, rows as u32 * cell.height);
+    let used_cols = thumb.width().div_ceil(cell.width).clamp(1, cols as u32) as u16;
+    let used_rows = thumb.height().div_ceil(cell.height).clamp(1, rows as u32) as u16;
+    let data = match protocol {
+        GraphicsProtocol::Kitty => kitty(&png(&thumb)?, used_cols, used_rows, slot),
+        GraphicsProtocol::ITerm2 => iterm2(&png(&thumb)?, used_cols, used_rows),
+        _ => sixel(&thumb.to_rgb8()),
+    };
+    Ok(PreviewImage::Graphic {
+        data,
+        cols: used_cols,
+        rows: used_rows,
+    })
+}
+
+fn png(image: &DynamicImage) -> Result<Vec<u8>> {
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)?;
+    Ok(png)
+}
+
+/// Transmit and display a PNG scaled to `cols` × `rows` cells, replacing the
+/// image shown before in `slot`; the cursor is not moved
+pub fn kitty(png: &[u8], cols: u16, rows: u16, slot: PreviewSlot) -> String {
+    let payload = STANDARD.encode(png);
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(KITTY_CHUNK).collect();
+
+    let mut data = kitty_delete(slot);
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        if i == 0 {
+            let _ = write!(
+                data,
+                "\x1b_Ga=T,f=100,t=d,q=2,C=1,i={},c={},r={},m={};",
+                slot.kitty_id(),
+                cols,
+                rows,
+                more
+            );
+        } else {
+            let _ = write!(data, "\x1b_Gm={};", more);
+        }
+        // base64 is ASCII
+        data.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        data.push_str("\x1b\\");
+    }
+    data
+}
+
+/// iTerm2 inline image scaled to `cols` × `rows` cells
+pub fn iterm2(png: &[u8], cols: u16, rows: u16) -> String {
+    format!(
+        "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=1:{}\x07",
+        png.len(),
+        cols,
+        rows,
+        STANDARD.encode(png)
+    )
+}
+
+/// Sixel image with a fixed 6×6×6 color cube palette
+pub fn sixel(image: &RgbImage) -> String {
+    let (width, height) = image.dimensions();
+    let color = |x: u32, y: u32| {
+        let [r, g, b] = image.get_pixel(x, y).0;
+        let level = |c: u8| (c as u16 * 5 + 127) / 255;
+        level(r) * 36 + level(g) * 6 + level(b)
+    };
+
+    // P2=1: pixels left at 0 stay transparent
+    let mut data = format!("\x1bP0;1;0q\"1;1;{};{}", width, height);
+    for index in 0..216u16 {
+        let percent = |level: u16| level * 20;
+        let _ = write!(data, "#{};2;{};{};{}", index, percent(index / 36), percent(index / 6 % 6), percent(index % 6));
+    }
+
+    for band in (0..height).step_by(6) {
+        // Per color, the sixel bits of every column in this band
+        let mut columns: BTreeMap<u16, Vec<u8>> = BTreeMap::new();
+        for dy in 0..(height - band).min(6) {
+            for x in 0..width {
+                columns.entry(color(x, band + dy)).or_insert_with(|| vec![0; width as usize])[x as usize] |= 1 << dy;
+            }
+        }
+
+        for (n, (index, bits)) in columns.iter().enumerate() {
+            if n > 0 {
+                // Back to the start of the band for the next color
+                data.push('$');
+            }
+            let _ = write!(data, "#{}", index);
+            let mut run = (bits[0], 0usize);
+            for &b in bits {
+                if b == run.0 {
+                    run.1 += 1;
+                } else {
+                    push_sixels(&mut data, run);
+                    run = (b, 1);
+                }
+            }
+            push_sixels(&mut data, run);
+        }
+        data.push('-');
+    }
+    data.push_str("\x1b\\");
+    data
+}
+
+/// Append `count` copies of the sixel for `bits`, run-length encoded
+fn push_sixels(data: &mut String, (bits, count): (u8, usize)) {
+    let sixel = (b'?' + bits) as char;
+    match count {
+        0 => {}
+        1..=3 => data.extend(std::iter::repeat_n(sixel, count)),
+        _ => {
+            let _ = write!(data, "!{}{}", count, sixel);
+        }
+    }
+}
+
+/// One `▀` per cell: the upper pixel is the foreground, the lower the background
+pub fn half_blocks(image: &RgbImage) -> Vec<Line<'static>> {
+    let (width, height) = image.dimensions();
+    let rgb = |x: u32, y: u32| {
+        let [r, g, b] = image.get_pixel(x, y).0;
+        Color::Rgb(r, g, b)
+    };
+
+    (0..height)
+        .step_by(2)
+        .map(|y| {
+            let spans: Vec<Span<'static>> = (0..width)
+                .map(|x| {
+                    let mut style = Style::default().fg(rgb(x, y));
+                    if y + 1 < height {
+                        style = style.bg(rgb(x, y + 1));
+                    }
+                    Span::styled("▀", style)
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    #[test]
+    fn test_sixel_bands_and_run_lengths() {
+        // 8 columns × 7 rows: black with a white bottom row
+        let image = RgbImage::from_fn(8, 7, |_, y| if y == 6 { Rgb([255, 255, 255]) } else { Rgb([0, 0, 0]) });
+        let data = sixel(&image);
+
+        assert!(data.starts_with("\x1bP0;1;0q\"1;1;8;7#0;2;0;0;0"));
+        assert!(data.ends_with("\x1b\\"));
+        // First band: all six rows black, run-length encoded
+        assert!(data.contains("#0!8~-"));
+        // Second band: only its top row, white
+        assert!(data.contains("#215!8@-"));
+    }
+
+    #[test]
+    fn test_kitty_chunks_and_half_blocks() {
+        let png = vec![0u8; 5000];
+        let data = kitty(&png, 20, 10, PreviewSlot::Secondary);
+        assert!(data.starts_with("\x1b_Ga=d,d=I,i=2,q=2\x1b\\"));
+        assert!(data.contains("\x1b_Ga=T,f=100,t=d,q=2,C=1,i=2,c=20,r=10,m=1;"));
+        assert_eq!(data.matches("\x1b_Gm=").count(), 1);
+        assert!(data.contains("\x1b_Gm=0;"));
+
+        let image = RgbImage::from_fn(3, 3, |x, _| Rgb([x as u8 * 100, 0, 0]));
+        let lines = half_blocks(&image);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].spans.len(), 3);
+        assert_eq!(lines[0].spans[2].style.fg, Some(Color::Rgb(200, 0, 0)));
+        assert_eq!(lines[1].spans[0].style.bg, None);
+    }
+}
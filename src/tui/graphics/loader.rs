@@ -0,0 +1,148 @@
+//! Background decoding of previews
+//!
+//! Decoding a large photo takes far longer than a frame, so the TUI hands
+//! requests to a worker thread and picks up finished previews when it polls.
+//! Requests that pile up while the worker is busy are skipped in favour of
+//! the newest one for each slot, so holding down an arrow key never queues a
+//! backlog.
+
+use super::encode::encode;
+use super::{CellSize, GraphicsProtocol, Preview, PreviewImage, PreviewSlot};
+use crate::models::{FileId, FileInfo};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+struct Request {
+    slot: PreviewSlot,
+    file: FileId,
+    path: PathBuf,
+    cols: u16,
+    rows: u16,
+}
+
+pub struct PreviewLoader {
+    protocol: GraphicsProtocol,
+    requests: Sender<Request>,
+    results: Receiver<Preview>,
+    /// File and area of the last request per slot, to avoid asking twice
+    requested: [Option<(FileId, u16, u16)>; 2],
+}
+
+impl PreviewLoader {
+    /// Start the worker thread
+    pub fn new(protocol: GraphicsProtocol, cell: CellSize) -> Self {
+        let (requests, inbox) = mpsc::channel::<Request>();
+        let (outbox, results) = mpsc::channel();
+
+        thread::spawn(move || {
+            while let Ok(first) = inbox.recv() {
+                let mut newest: [Option<Request>; 2] = [None, None];
+                for request in std::iter::once(first).chain(inbox.try_iter()) {
+                    let slot = request.slot.index();
+                    newest[slot] = Some(request);
+                }
+                for request in newest.into_iter().flatten() {
+                    if outbox.send(load(&request, protocol, cell)).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        Self {
+            protocol,
+            requests,
+            results,
+            requested: [None, None],
+        }
+    }
+
+    pub fn protocol(&self) -> GraphicsProtocol {
+        self.protocol
+    }
+
+    /// Ask for a preview of `file` sized for `cols` × `rows` cells in the pane
+    /// of `slot`, unless that was the slot's last request
+    pub fn request(&mut self, slot: PreviewSlot, file: &FileInfo, cols: u16, rows: u16) {
+        let requested = &mut self.requested[slot.index()];
+        if cols == 0 || rows == 0 || *requested == Some((file.id, cols, rows)) {
+            return;
+        }
+        *requested = Some((file.id, cols, rows));
+        let _ = self.requests.send(Request {
+            slot,
+            file: file.id,
+            path: file.path.clone(),
+            cols,
+            rows,
+        });
+    }
+
+    /// Previews finished since the last call, oldest first
+    pub fn try_recv(&self) -> Vec<Preview> {
+        self.results.try_iter().collect()
+    }
+}
+
+fn load(request: &Request, protocol: GraphicsProtocol, cell: CellSize) -> Preview {
+    let (dimensions, image) = match image::open(&request.path) {
+        Ok(img) => {
+            let dimensions = Some((img.width(), img.height()));
+            let image = encode(&img, protocol, request.cols, request.rows, cell, request.slot)
+                .unwrap_or_else(|e| PreviewImage::Unavailable(e.to_string()));
+            (dimensions, image)
+        }
+        Err(e) => (None, PreviewImage::Unavailable(e.to_string())),
+    };
+    Preview {
+        slot: request.slot,
+        file: request.file,
+        cols: request.cols,
+        rows: request.rows,
+        dimensions,
+        image,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::file_info::{ImageFormat, MediaType};
+    use image::{Rgb, RgbImage};
+    use std::time::{Duration, Instant, UNIX_EPOCH};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_loads_in_background() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("wide.png");
+        RgbImage::from_pixel(400, 100, Rgb([0, 128, 255])).save(&path).unwrap();
+        let mut file = FileInfo::new(path, 0, UNIX_EPOCH, MediaType::Image(ImageFormat::Png));
+        file.id = FileId(7);
+
+        let mut loader = PreviewLoader::new(GraphicsProtocol::HalfBlocks, CellSize::default());
+        loader.request(PreviewSlot::Secondary, &file, 40, 20);
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let preview = loop {
+            if let Some(preview) = loader.try_recv().pop() {
+                break preview;
+            }
+            assert!(Instant::now() < deadline, "preview never arrived");
+            thread::sleep(Duration::from_millis(10));
+        };
+
+        assert_eq!(preview.slot, PreviewSlot::Secondary);
+        assert!(preview.fits(FileId(7), 40, 20));
+        assert_eq!(preview.dimensions, Some((400, 100)));
+        // 4:1 image in 40 columns: 10 pixels high, 5 rows of half blocks
+        match preview.image {
+            PreviewImage::Blocks(lines) => {
+                assert_eq!(lines.len(), 5);
+                assert_eq!(lines[0].spans.len(), 40);
+            }
+            other => panic!("expected half blocks, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! Inline image previews
+//!
+//! The selected image is decoded and downscaled on a background thread by
+//! [`PreviewLoader`], then encoded for the terminal's graphics protocol. When
+//! no protocol is available the image is drawn with half-block characters.
+
+pub mod encode;
+pub mod loader;
+
+pub use loader::PreviewLoader;
+
+use crate::models::FileId;
+use ratatui::text::Line;
+
+/// `--preview` choices
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum PreviewMode {
+    /// Detect the terminal's graphics protocol
+    #[default]
+    Auto,
+    /// Kitty graphics protocol (kitty, Ghostty)
+    Kitty,
+    /// iTerm2 inline images (iTerm2, WezTerm)
+    Iterm,
+    /// Sixel graphics (foot, mlterm, xterm -ti vt340)
+    Sixel,
+    /// Unicode half blocks, works everywhere with true color
+    Blocks,
+    /// No preview pane
+    Off,
+}
+
+impl PreviewMode {
+    pub fn protocol(&self) -> Option<GraphicsProtocol> {
+        match self {
+            PreviewMode::Auto => Some(GraphicsProtocol::detect()),
+            PreviewMode::Kitty => Some(GraphicsProtocol::Kitty),
+            PreviewMode::Iterm => Some(GraphicsProtocol::ITerm2),
+            PreviewMode::Sixel => Some(GraphicsProtocol::Sixel),
+            PreviewMode::Blocks => Some(GraphicsProtocol::HalfBlocks),
+            PreviewMode::Off => None,
+        }
+    }
+}
+
+/// How an image is put on the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphicsProtocol {
+    Kitty,
+    ITerm2,
+    Sixel,
+    HalfBlocks,
+}
+
+impl GraphicsProtocol {
+    /// Guess the protocol from the environment the terminal sets
+    pub fn detect() -> Self {
+        Self::from_env(|name| std::env::var(name).ok())
+    }
+
+    fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        // tmux would need every sequence wrapped in a passthrough
+        if var("TMUX").is_some() {
+            return GraphicsProtocol::HalfBlocks;
+        }
+
+        let term = var("TERM").unwrap_or_default();
+        let program = var("TERM_PROGRAM").unwrap_or_default();
+        if var("KITTY_WINDOW_ID").is_some() || term.contains("kitty") || program == "ghostty" {
+            GraphicsProtocol::Kitty
+        } else if program == "iTerm.app" || program == "WezTerm" || var("LC_TERMINAL").as_deref() == Some("iTerm2") {
+            GraphicsProtocol::ITerm2
+        } else if ["foot", "mlterm", "sixel"].iter().any(|name| term.contains(name)) || program == "contour" {
+            GraphicsProtocol::Sixel
+        } else {
+            GraphicsProtocol::HalfBlocks
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GraphicsProtocol::Kitty => "Kitty",
+            GraphicsProtocol::ITerm2 => "iTerm2",
+            GraphicsProtocol::Sixel => "Sixel",
+            GraphicsProtocol::HalfBlocks => "半块字符",
+        }
+    }
+}
+
+/// Preview pane an image is for: the file details, or the right-hand side of
+/// compare mode. Each slot keeps only its newest request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewSlot {
+    Primary,
+    Secondary,
+}
+
+impl PreviewSlot {
+    pub const ALL: [PreviewSlot; 2] = [PreviewSlot::Primary, PreviewSlot::Secondary];
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// Kitty image id, so the two panes replace only their own image
+    pub fn kitty_id(&self) -> u32 {
+        self.index() as u32 + 1
+    }
+}
+
+/// Pixel size of one terminal cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for CellSize {
+    fn default() -> Self {
+        Self { width: 10, height: 20 }
+    }
+}
+
+impl CellSize {
+    /// Ask the terminal, falling back to 10×20 when it does not report pixels
+    pub fn query() -> Self {
+        match crossterm::terminal::window_size() {
+            Ok(size) if size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0 => Self {
+                width: (size.width / size.columns) as u32,
+                height: (size.height / size.rows) as u32,
+            },
+            _ => Self::default(),
+        }
+    }
+}
+
+/// A decoded preview of one file, sized for an area of `cols` × `rows` cells
+#[derive(Debug, Clone)]
+pub struct Preview {
+    pub slot: PreviewSlot,
+    pub file: FileId,
+    pub cols: u16,
+    pub rows: u16,
+    /// Pixel size of the original image
+    pub dimensions: Option<(u32, u32)>,
+    pub image: PreviewImage,
+}
+
+impl Preview {
+    /// Whether this preview was made for `file` in an area of `cols` × `rows`
+    pub fn fits(&self, file: FileId, cols: u16, rows: u16) -> bool {
+        self.file == file && self.cols == cols && self.rows == rows
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum PreviewImage {
+    /// Escape sequence that draws the image from the top-left cell of a
+    /// `cols` × `rows` area
+    Graphic { data: String, cols: u16, rows: u16 },
+    /// Half-block rendering, one line per row of cells
+    Blocks(Vec<Line<'static>>),
+    /// Why the file could not be previewed
+    Unavailable(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_protocol_from_env() {
+        let detect = |vars: &[(&str, &str)]| {
+            GraphicsProtocol::from_env(|name| {
+                vars.iter().find(|(k, _)| *k == name).map(|(_, v)| v.to_string())
+            })
+        };
+
+        assert_eq!(detect(&[("TERM", "xterm-kitty")]), GraphicsProtocol::Kitty);
+        assert_eq!(detect(&[("TERM_PROGRAM", "iTerm.app")]), GraphicsProtocol::ITerm2);
+        assert_eq!(detect(&[("TERM", "foot")]), GraphicsProtocol::Sixel);
+        assert_eq!(detect(&[("TERM", "xterm-256color")]), GraphicsProtocol::HalfBlocks);
+        assert_eq!(detect(&[("TERM", "xterm-kitty"), ("TMUX", "/tmp/tmux")]), GraphicsProtocol::HalfBlocks);
+    }
+}
@@ -1,7 +1,8 @@
 pub mod app;
 pub mod ui;
 pub mod event;
+pub mod graphics;
 
-pub use app::{App, Mode};
+pub use app::{App, Mode, Operation, PendingAction, StatusKind};
 pub use ui::MainLayout;
 pub use event::key_handler::KeyAction;
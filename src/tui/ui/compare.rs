@@ -0,0 +1,119 @@
+use chrono::{DateTime, Local};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::models::FileInfo;
+use crate::tui::graphics::PreviewSlot;
+use crate::tui::ui::PreviewWidget;
+use crate::tui::App;
+use crate::utils::format_size;
+
+pub struct CompareWidget;
+
+impl CompareWidget {
+    /// Two files of the current group side by side, with the fields that
+    /// differ between them highlighted
+    pub fn render(f: &mut Frame, app: &mut App, area: Rect) {
+        let Some(group) = app.current_group() else {
+            return;
+        };
+        let pair = app.compare;
+        let (Some(left), Some(right)) = (group.files.get(pair.left), group.files.get(pair.right)) else {
+            return;
+        };
+        let left_facts = facts(left);
+        let right_facts = facts(right);
+        let count = group.file_count();
+        let original = group.recommended_original;
+
+        let halves = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(area);
+
+        let sides = [
+            (pair.left, "◀ 左", &left_facts, &right_facts, PreviewSlot::Primary, halves[0]),
+            (pair.right, "右 ▶", &right_facts, &left_facts, PreviewSlot::Secondary, halves[1]),
+        ];
+        for (index, side, own, other, slot, half) in sides {
+            let kept = index == original;
+            let border = Style::default().fg(if kept { Color::Green } else { Color::White });
+            let mut title = format!(" {} · 文件 {}/{} ", side, index + 1, count);
+            if kept {
+                title.push_str("✓保留 ");
+            }
+
+            let info_height = own.len() as u16 + 2;
+            let (preview_area, info_area) = if app.preview_visible() {
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(0), Constraint::Length(info_height)])
+                    .split(half);
+                (Some(chunks[0]), chunks[1])
+            } else {
+                (None, Rect { height: info_height.min(half.height), ..half })
+            };
+
+            let lines: Vec<Line> = own
+                .iter()
+                .zip(other.iter())
+                .map(|((label, value), (_, other_value))| {
+                    // 与另一侧不同的字段高亮
+                    let style = if value != other_value {
+                        Style::default().fg(Color::Yellow).bold()
+                    } else {
+                        Style::default().fg(Color::White)
+                    };
+                    // 标签是全角字符，按两列宽对齐
+                    let padding = " ".repeat(10usize.saturating_sub(label.chars().count() * 2));
+                    Line::from(vec![
+                        Span::styled(format!("{}{}", label, padding), Style::default().fg(Color::Cyan)),
+                        Span::styled(value.clone(), style),
+                    ])
+                })
+                .collect();
+
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .border_style(border)
+                .title(title)
+                .title_style(border.bold());
+            match preview_area {
+                Some(preview_area) => {
+                    PreviewWidget::render_file(f, app, preview_area, block, slot, index);
+                    let info = Paragraph::new(lines)
+                        .block(Block::default().borders(Borders::ALL).border_style(border))
+                        .wrap(Wrap { trim: false });
+                    f.render_widget(info, info_area);
+                }
+                None => {
+                    let info = Paragraph::new(lines).block(block).wrap(Wrap { trim: false });
+                    f.render_widget(info, info_area);
+                }
+            }
+        }
+    }
+}
+
+/// Labelled fields compared between the two sides
+fn facts(file: &FileInfo) -> [(&'static str, String); 5] {
+    let dimensions = match file.dimensions {
+        Some((width, height)) => format!("{} × {}", width, height),
+        None => "—".to_string(),
+    };
+    let modified = DateTime::<Local>::from(file.modified)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+    [
+        ("尺寸", dimensions),
+        ("大小", format!("{}（{} 字节）", format_size(file.size), file.size)),
+        ("修改时间", modified),
+        ("格式", file.file_type.format_name().to_uppercase()),
+        ("路径", file.path.display().to_string()),
+    ]
+}
@@ -0,0 +1,92 @@
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::tui::App;
+use crate::utils::format_size;
+
+pub struct ConfirmWidget;
+
+impl ConfirmWidget {
+    pub fn render(f: &mut Frame, app: &App) {
+        let Some(pending) = &app.pending else {
+            return;
+        };
+        let size = f.area();
+
+        // 居中的确认对话框
+        let width = (size.width * 7 / 10).max(40).min(size.width);
+        let height = (size.height * 6 / 10).max(10).min(size.height);
+        let popup_area = Rect {
+            x: (size.width - width) / 2,
+            y: (size.height - height) / 2,
+            width,
+            height,
+        };
+
+        f.render_widget(Clear, popup_area);
+
+        let files: Vec<_> = pending
+            .locations
+            .iter()
+            .filter_map(|&(g, i)| app.duplicate_groups.get(g).and_then(|group| group.files.get(i)))
+            .collect();
+        let total: u64 = files.iter().map(|f| f.size).sum();
+
+        let mut lines = vec![
+            Line::from(vec![
+                Span::styled("确定要将以下 ", Style::default()),
+                Span::styled(format!("{} 个文件", files.len()), Style::default().fg(Color::Yellow).bold()),
+                Span::styled(format!("（{}）", format_size(total)), Style::default().fg(Color::Yellow)),
+                Span::styled(app.pending_description(), Style::default().fg(Color::Red).bold()),
+                Span::styled(" 吗?", Style::default()),
+            ]),
+            Line::from(""),
+        ];
+
+        // 边框、标题行、空行和底部提示占去的行数
+        let room = height.saturating_sub(7) as usize;
+        let shown = if files.len() > room { room.saturating_sub(1) } else { files.len() };
+        for file in &files[..shown] {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {:>8}  ", format_size(file.size)), Style::default().fg(Color::DarkGray)),
+                Span::styled(file.path.display().to_string(), Style::default().fg(Color::White)),
+            ]));
+        }
+        if shown < files.len() {
+            lines.push(Line::from(Span::styled(
+                format!("  … 还有 {} 个文件", files.len() - shown),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+
+        lines.push(Line::from(""));
+        if app.dry_run {
+            lines.push(Line::from(Span::styled(
+                "🧪 演练模式：只检查并记录，不会改动文件",
+                Style::default().fg(Color::Cyan),
+            )));
+        }
+        lines.push(Line::from(vec![
+            Span::styled("[y / Enter]", Style::default().fg(Color::Green).bold()),
+            Span::styled(" 确认   ", Style::default()),
+            Span::styled("[n / Esc]", Style::default().fg(Color::Red).bold()),
+            Span::styled(" 取消", Style::default()),
+        ]).alignment(Alignment::Center));
+
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title(" ⚠️  确认操作 ")
+                    .title_style(Style::default().fg(Color::Yellow).bold())
+                    .borders(Borders::ALL),
+            )
+            .wrap(Wrap { trim: false });
+
+        f.render_widget(paragraph, popup_area);
+    }
+}
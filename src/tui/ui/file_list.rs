@@ -1,13 +1,281 @@
-// This file is intentionally left minimal as the list rendering
-// is handled in main_layout.rs for better layout coordination
+// Scrolling group and file tables
+//
+// Only the rows inside the viewport are built, so lists with thousands of
+// groups cost no more per frame than a screenful.
 
-use ratatui::Frame;
+use std::ops::Range;
+
+use ratatui::{
+    layout::{Constraint, Margin, Rect},
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Borders, Cell, Paragraph, Row, Scrollbar, ScrollbarOrientation, ScrollbarState, Table, TableState},
+    Frame,
+};
+
+use crate::models::GroupKind;
 use crate::tui::App;
+use crate::utils::format_size;
+
+/// Scroll position of a list, kept between frames
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ListScroll {
+    /// Index of the first visible row
+    pub offset: usize,
+    /// Rows that fit in the viewport at the last render
+    pub height: usize,
+}
+
+impl ListScroll {
+    /// Rows to show in a viewport of `height` rows, scrolling just enough to
+    /// keep `selected` visible
+    pub fn window(&mut self, selected: usize, len: usize, height: usize) -> Range<usize> {
+        let height = height.max(1);
+        self.height = height;
+        if selected < self.offset {
+            self.offset = selected;
+        } else if selected >= self.offset + height {
+            self.offset = selected + 1 - height;
+        }
+        self.offset = self.offset.min(len.saturating_sub(height));
+        self.offset..(self.offset + height).min(len)
+    }
+
+    /// Rows to move for PageUp / PageDown
+    pub fn page(&self) -> usize {
+        self.height.max(1)
+    }
+}
 
 pub struct FileListWidget;
 
 impl FileListWidget {
-    pub fn render(_f: &mut Frame, _app: &App) {
-        // Rendering handled in main_layout.rs
+    /// Duplicate groups, one row each
+    pub fn render_groups(f: &mut Frame, app: &mut App, area: Rect) {
+        let len = app.group_count();
+        let selected = app.selected_group;
+        // 边框和表头占去 3 行
+        let range = app.group_scroll.window(selected, len, area.height.saturating_sub(3) as usize);
+        let app = &*app;
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" 📁 重复文件组 ({}) ", len))
+            .title_bottom(position(selected, len));
+
+        if len == 0 {
+            let paragraph = Paragraph::new("  暂无重复文件")
+                .style(Style::default().fg(Color::DarkGray))
+                .block(block);
+            f.render_widget(paragraph, area);
+            return;
+        }
+
+        let rows = range.clone().map(|i| {
+            let group = &app.duplicate_groups[i];
+            let marked_in_group = app.marked_count_in_group(i);
+            let (kind_tag, kind_color) = match group.kind {
+                GroupKind::Exact => ("≡", Color::Green),
+                GroupKind::Similar => ("≈", Color::Cyan),
+            };
+            let mark_indicator = if marked_in_group > 0 {
+                format!("[{}]", marked_in_group)
+            } else {
+                String::new()
+            };
+            let reclaimed = app.reclaimed_in_group(i);
+
+            Row::new(vec![
+                Cell::from(format!("#{}", i + 1)).style(Style::default().fg(Color::Yellow).bold()),
+                Cell::from(kind_tag).style(Style::default().fg(kind_color).bold()),
+                Cell::from(format!("{:>4}", group.file_count())),
+                Cell::from(format!("{:>8}", format_size(group.total_size()))),
+                Cell::from(mark_indicator).style(Style::default().fg(Color::Magenta).bold()),
+                Cell::from(format!("重复{:>2}个", group.file_count().saturating_sub(1)))
+                    .style(Style::default().fg(Color::Red)),
+                Cell::from(if reclaimed > 0 {
+                    format!("已回收 {}", format_size(reclaimed))
+                } else {
+                    String::new()
+                })
+                .style(Style::default().fg(Color::Green)),
+            ])
+        });
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(6),
+                Constraint::Length(4),
+                Constraint::Length(6),
+                Constraint::Length(9),
+                Constraint::Length(5),
+                Constraint::Length(8),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(
+            Row::new(["序号", "类型", "文件数", "    大小", "标记", "", ""])
+                .style(Style::default().fg(Color::Cyan).bold()),
+        )
+        .block(block)
+        .row_highlight_style(Style::default().bg(Color::Blue).fg(Color::White).bold())
+        .highlight_symbol("▶ ");
+
+        let mut state = TableState::default().with_selected(selected - range.start);
+        f.render_stateful_widget(table, area, &mut state);
+        render_scrollbar(f, area, selected, len, range.len());
+    }
+
+    /// Files of the selected group, one row each
+    pub fn render_files(f: &mut Frame, app: &mut App, area: Rect, title: String) {
+        let len = app.file_count();
+        let selected = app.selected_file;
+        let range = app.file_scroll.window(selected, len, area.height.saturating_sub(3) as usize);
+        let app = &*app;
+        let Some(group) = app.current_group() else {
+            return;
+        };
+
+        let rows = range.clone().map(|i| {
+            let file = &group.files[i];
+            let is_original = i == group.recommended_original;
+            let is_marked = app.is_marked(file.id);
+
+            // 状态标记
+            let (status_mark, status_color) = if is_original {
+                ("✓原始", Color::Green)
+            } else if is_marked {
+                ("[✓标记]", Color::Magenta)
+            } else {
+                (" 重复", Color::DarkGray)
+            };
+            let style = if is_original {
+                Style::default().fg(Color::Green).bold()
+            } else {
+                Style::default()
+            };
+
+            Row::new(vec![
+                Cell::from(format!("{}/{}", i + 1, len)).style(Style::default().fg(Color::Yellow)),
+                Cell::from(status_mark).style(Style::default().fg(status_color).bold()),
+                Cell::from(file.filename().to_string()).style(style),
+                Cell::from(format!("{:>8}", format_size(file.size))).style(style),
+            ])
+        });
+
+        // 选中行在已标记时用洋红色高亮
+        let highlight = if app.is_current_file_marked() { Color::Magenta } else { Color::Blue };
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(9),
+                Constraint::Length(8),
+                Constraint::Fill(1),
+                Constraint::Length(9),
+            ],
+        )
+        .header(
+            Row::new(["序号", "状态", "文件名", "    大小"])
+                .style(Style::default().fg(Color::Cyan).bold()),
+        )
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_bottom(position(selected, len)),
+        )
+        .row_highlight_style(Style::default().bg(highlight).fg(Color::White).bold())
+        .highlight_symbol("▶ ");
+
+        let mut state = TableState::default().with_selected(selected - range.start);
+        f.render_stateful_widget(table, area, &mut state);
+        render_scrollbar(f, area, selected, len, range.len());
+    }
+}
+
+/// "12/3400" in the bottom right corner of the border
+fn position(selected: usize, len: usize) -> Line<'static> {
+    let text = if len == 0 { " 0/0 ".to_string() } else { format!(" {}/{} ", selected + 1, len) };
+    Line::from(text).right_aligned()
+}
+
+/// Scrollbar on the right border, below the header row, when not every row fits
+fn render_scrollbar(f: &mut Frame, area: Rect, selected: usize, len: usize, visible: usize) {
+    if len <= visible {
+        return;
+    }
+    let track = area.inner(Margin { vertical: 1, horizontal: 0 });
+    let track = Rect {
+        y: track.y + 1,
+        height: track.height.saturating_sub(1),
+        ..track
+    };
+    let mut state = ScrollbarState::new(len)
+        .position(selected)
+        .viewport_content_length(visible);
+    f.render_stateful_widget(
+        Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None),
+        track,
+        &mut state,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_keeps_selection_visible() {
+        let mut scroll = ListScroll::default();
+        assert_eq!(scroll.window(0, 1000, 10), 0..10);
+        // Moving inside the window does not scroll
+        assert_eq!(scroll.window(9, 1000, 10), 0..10);
+        assert_eq!(scroll.window(10, 1000, 10), 1..11);
+        assert_eq!(scroll.window(500, 1000, 10), 491..501);
+        assert_eq!(scroll.window(495, 1000, 10), 491..501);
+        assert_eq!(scroll.window(3, 1000, 10), 3..13);
+
+        // No blank rows once the list shrinks below the viewport
+        assert_eq!(scroll.window(3, 5, 10), 0..5);
+        assert_eq!(scroll.window(0, 0, 10), 0..0);
+        assert_eq!(scroll.page(), 10);
+    }
+
+    #[test]
+    fn test_group_list_renders_visible_window() {
+        use crate::models::{DuplicateGroup, FileInfo};
+        use crate::models::file_info::{ImageFormat, MediaType};
+        use ratatui::{backend::TestBackend, Terminal};
+        use std::path::PathBuf;
+        use std::time::UNIX_EPOCH;
+
+        let file = |name: &str| FileInfo::new(PathBuf::from(name), 10, UNIX_EPOCH, MediaType::Image(ImageFormat::Jpeg));
+        let groups = (0..1000)
+            .map(|i| DuplicateGroup::new(i, vec![file("/a.jpg"), file("/b.jpg")]))
+            .collect();
+        let mut app = App::new(groups);
+        app.selected_group = 499;
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 13)).unwrap();
+        terminal
+            .draw(|f| FileListWidget::render_groups(f, &mut app, f.area()))
+            .unwrap();
+
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert_eq!(app.group_scroll.offset, 490);
+        assert!(screen.contains("▶ #500"));
+        assert!(screen.contains("#491"));
+        assert!(!screen.contains("#490 "));
+        assert!(screen.contains("500/1000"));
     }
 }
@@ -32,7 +32,7 @@ impl HelpWidget {
             Line::from("  ↑ / ↓ 或 j / k     在重复组之间上下移动"),
             Line::from("  Tab                在当前组的文件间切换"),
             Line::from("  Shift + Tab         反向切换文件"),
-            Line::from("  Page Up / Down     快速跳转 5 个重复组"),
+            Line::from("  Page Up / Down     按页翻动重复组列表"),
             Line::from("  Home / End         跳转到第一个 / 最后一个组"),
             Line::from(""),
             Line::from(vec![
@@ -40,10 +40,19 @@ impl HelpWidget {
                     Style::default().fg(Color::Yellow).bold()),
             ]),
             Line::from("  o                  用系统默认应用打开选中的文件"),
+            Line::from("  p                  显示/隐藏图片预览"),
+            Line::from("  c                  并排比较推荐保留的文件和当前文件"),
+            Line::from("                     （Tab 换右侧文件，1 / 2 保留左侧 / 右侧，Esc 返回）"),
             Line::from("  d                  删除当前选中的文件（需确认）"),
             Line::from("  Space (空格)        标记/取消标记文件"),
             Line::from("  D                  删除所有已标记的文件（需确认）"),
+            Line::from("  l                  用指向原始文件的硬链接替换当前文件（需确认）"),
+            Line::from("  L                  用硬链接替换所有已标记的文件（需确认）"),
+            Line::from("  r / R              用 reflink（写时复制）替换当前 / 所有已标记的文件"),
+            Line::from("  s / S              用指向原始文件的符号链接替换当前 / 所有已标记的文件"),
+            Line::from("  x                  把已标记的文件导出为待审阅的 shell 脚本（不改动文件）"),
             Line::from("  u                  取消所有标记"),
+            Line::from("  z                  撤销最近一次删除（从回收站恢复）"),
             Line::from(""),
             Line::from(vec![
                 Span::styled("━━━ 其他操作 ━━━",
@@ -58,9 +67,16 @@ impl HelpWidget {
                     Style::default().fg(Color::Green).bold()),
             ]),
             Line::from("  • 绿色 ✓ 标记表示推荐的原始文件"),
+            Line::from("  • ≡ 表示内容完全相同的文件组，≈ 表示相似图片组"),
             Line::from("  • 只删除重复文件，保留原始文件以节省空间"),
             Line::from("  • 可以先标记多个文件，然后按 D 批量删除"),
-            Line::from("  • 删除操作需要输入 y 确认，请谨慎操作"),
+            Line::from("  • 删除的文件会移至系统回收站，在确认对话框中按 y 确认，结果显示在底部状态栏"),
+            Line::from("  • 使用 --quarantine DIR 启动时改为移入隔离目录，并记录在 manifest.jsonl 中"),
+            Line::from("  • 删除前会重新核对文件与保留的原始文件，内容变化时拒绝删除"),
+            Line::from("  • 硬链接保留文件路径但只占一份空间，仅适用于完全相同 (≡) 且在同一文件系统的文件"),
+            Line::from("  • reflink 需要 btrfs、XFS 等写时复制文件系统，各文件仍可独立修改"),
+            Line::from("  • 符号链接同样仅适用于完全相同 (≡) 的文件，可跨文件系统，默认使用相对路径（--symlink-target absolute 改为绝对路径）"),
+            Line::from("  • 导出的脚本写在当前目录，检查无误后用 sh 执行；设置了隔离目录时脚本改用 mv"),
             Line::from(""),
             Line::from(""),
             Line::from(vec![
@@ -6,13 +6,14 @@ use ratatui::{
     Frame,
 };
 
-use crate::tui::App;
+use crate::tui::{App, StatusKind};
+use crate::tui::ui::{CompareWidget, ConfirmWidget, FileListWidget, PreviewWidget};
 use crate::utils::format_size;
 
 pub struct MainLayout;
 
 impl MainLayout {
-    pub fn render(f: &mut Frame, app: &App) {
+    pub fn render(f: &mut Frame, app: &mut App) {
         // 如果是引导模式，显示引导界面
         if app.mode == crate::tui::Mode::Tutorial {
             Self::render_tutorial(f, app);
@@ -21,12 +22,13 @@ impl MainLayout {
 
         let size = f.area();
 
-        // Split into 4 parts: stats (top), main content (middle), help (bottom)
+        // Split into 4 parts: stats (top), main content (middle), status and help (bottom)
         let main_chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Length(5),  // Stats panel + tutorial hint
                 Constraint::Min(0),     // Main content
+                Constraint::Length(1),  // Status bar
                 Constraint::Length(3),  // Help text
             ])
             .split(size);
@@ -34,6 +36,13 @@ impl MainLayout {
         // Render stats panel at top
         Self::render_stats_panel(f, app, main_chunks[0]);
 
+        if app.mode == crate::tui::Mode::Compare {
+            CompareWidget::render(f, app, main_chunks[1]);
+            Self::render_status_bar(f, app, main_chunks[2]);
+            Self::render_compare_help(f, main_chunks[3]);
+            return;
+        }
+
         // Split main content into left (groups) and right (details)
         let content_chunks = Layout::default()
             .direction(Direction::Horizontal)
@@ -44,13 +53,27 @@ impl MainLayout {
             .split(main_chunks[1]);
 
         // Render group list on left
-        Self::render_group_list(f, app, content_chunks[0]);
+        FileListWidget::render_groups(f, app, content_chunks[0]);
+
+        // Render file details on right, next to the preview if shown
+        if app.preview_visible() && app.current_group().is_some() {
+            let detail_chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+                .split(content_chunks[1]);
+            Self::render_file_details(f, app, detail_chunks[0]);
+            PreviewWidget::render(f, app, detail_chunks[1]);
+        } else {
+            Self::render_file_details(f, app, content_chunks[1]);
+        }
 
-        // Render file details on right
-        Self::render_file_details(f, app, content_chunks[1]);
+        // Render status bar and help text at bottom
+        Self::render_status_bar(f, app, main_chunks[2]);
+        Self::render_help_text(f, main_chunks[3]);
 
-        // Render help text at bottom
-        Self::render_help_text(f, main_chunks[2]);
+        if app.mode == crate::tui::Mode::Confirm {
+            ConfirmWidget::render(f, app);
+        }
     }
 
     fn render_tutorial(f: &mut Frame, app: &App) {
@@ -135,6 +158,13 @@ impl MainLayout {
                     Style::default().fg(Color::Yellow).bold(),
                 ),
                 Span::raw(" • "),
+                Span::styled("已回收: ",
+                    Style::default().fg(Color::Green)),
+                Span::styled(
+                    format_size(app.total_reclaimed()),
+                    Style::default().fg(Color::Green).bold(),
+                ),
+                Span::raw(" • "),
                 Span::styled(
                     if marked_count > 0 {
                         format!("已标记 {} 个", marked_count)
@@ -162,11 +192,12 @@ impl MainLayout {
             ]),
         ];
 
+        let title = if app.dry_run { " 📈 统计 · 🧪 演练模式 " } else { " 📈 统计 " };
         let paragraph = Paragraph::new(stats)
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title(" 📈 统计 ")
+                    .title(title)
                     .title_style(Style::default().fg(Color::Cyan).bold()),
             )
             .alignment(Alignment::Center)
@@ -175,87 +206,16 @@ impl MainLayout {
         f.render_widget(paragraph, area);
     }
 
-    fn render_group_list(f: &mut Frame, app: &App, area: Rect) {
-        let title = format!(" 📁 重复文件组 ({}) ", app.group_count());
-
-        let mut lines = Vec::new();
-
-        // Add header
-        lines.push(Line::from(vec![
-            Span::styled(" 序号    文件数    大小      标记", Style::default().fg(Color::Cyan).bold()),
-        ]));
-        lines.push(Line::from("─".repeat(area.width.saturating_sub(2) as usize)));
-
-        if app.duplicate_groups.is_empty() {
-            lines.push(Line::from(vec![
-                Span::styled("  暂无重复文件",
-                    Style::default().fg(Color::DarkGray)),
-            ]));
-        } else {
-            for (i, group) in app.duplicate_groups.iter().enumerate() {
-                let is_selected = i == app.selected_group;
-
-                let style = if is_selected {
-                    Style::default().bg(Color::Blue).fg(Color::White).bold()
-                } else {
-                    Style::default()
-                };
-
-                let prefix = if is_selected { "▶" } else { "▪" };
-
-                let marked_in_group = app.marked_count_in_group(i);
-                let mark_indicator = if marked_in_group > 0 {
-                    format!("[{}]", marked_in_group)
-                } else {
-                    "   ".to_string()
-                };
-
-                let duplicate_count = group.file_count().saturating_sub(1);
-
-                let line = Line::from(vec![
-                    Span::styled(format!("{} ", prefix), style),
-                    Span::styled(
-                        format!("#{:2}", i + 1),
-                        Style::default().fg(Color::Yellow).bold(),
-                    ),
-                    Span::styled(
-                        format!("   {:>4}", group.file_count()),
-                        style,
-                    ),
-                    Span::styled(
-                        format!("  {:>8}", format_size(group.total_size())),
-                        style,
-                    ),
-                    Span::styled(
-                        format!("   {}", mark_indicator),
-                        Style::default()
-                            .fg(if marked_in_group > 0 {
-                                Color::Magenta
-                            } else {
-                                Color::DarkGray
-                            })
-                            .bold(),
-                    ),
-                    Span::styled(
-                        format!("  重复{:>2}个", duplicate_count),
-                        Style::default().fg(Color::Red),
-                    ),
-                ]);
-
-                lines.push(line);
-            }
-        }
-
-        let paragraph = Paragraph::new(lines)
-            .block(Block::default().borders(Borders::ALL).title(title));
-        f.render_widget(paragraph, area);
-    }
-
-    fn render_file_details(f: &mut Frame, app: &App, area: Rect) {
+    fn render_file_details(f: &mut Frame, app: &mut App, area: Rect) {
         if let Some(group) = app.current_group() {
+            let kind = match group.algorithm_name() {
+                Some(algorithm) => format!("{} · {}", group.kind.label(), algorithm),
+                None => group.kind.label().to_string(),
+            };
             let title = format!(
-                " 📄 组 #{} - 共 {} 个文件 ",
+                " 📄 组 #{} [{}] - 共 {} 个文件 ",
                 app.selected_group + 1,
+                kind,
                 group.file_count()
             );
 
@@ -266,81 +226,10 @@ impl MainLayout {
                 .split(area);
 
             // Render file list
-            let mut lines = Vec::new();
-
-            // Add header with file numbers
-            for (i, file) in group.files.iter().enumerate() {
-                let is_selected = i == app.selected_file;
-                let is_original = i == group.recommended_original;
-                let is_marked = app.is_current_file_marked() && is_selected;
-
-                // 不同的背景色表示不同状态
-                let style = if is_selected {
-                    if is_marked {
-                        Style::default().bg(Color::Magenta).fg(Color::White).bold()
-                    } else {
-                        Style::default().bg(Color::Blue).fg(Color::White).bold()
-                    }
-                } else if is_original {
-                    Style::default().fg(Color::Green).bold()
-                } else {
-                    Style::default()
-                };
-
-                let prefix = if is_selected { "▶" } else { " " };
-
-                // 状态标记
-                let status_mark = if is_original {
-                    "✓原始"
-                } else if is_marked {
-                    "[✓标记]"
-                } else {
-                    " 重复"
-                };
-
-                // 文件名和大小
-                let max_name_len = chunks[0]
-                    .width
-                    .saturating_sub(30) as usize;
-                let filename = if file.filename().len() > max_name_len {
-                    format!("...{}", &file.filename()[file.filename().len().saturating_sub(max_name_len)..])
-                } else {
-                    file.filename().to_string()
-                };
-
-                let file_num = format!("{}/{}", i + 1, group.file_count());
-
-                let line = Line::from(vec![
-                    Span::styled(format!("{} ", prefix), style),
-                    Span::styled(
-                        format!("{:<6}", file_num),
-                        Style::default().fg(Color::Yellow),
-                    ),
-                    Span::styled(status_mark,
-                        Style::default().fg(if is_original {
-                            Color::Green
-                        } else if is_marked {
-                            Color::Magenta
-                        } else {
-                            Color::DarkGray
-                        }).bold()),
-                    Span::styled(
-                        format!(" {:<width$}", filename, width = max_name_len),
-                        style,
-                    ),
-                    Span::styled(
-                        format!(" {:>8}", format_size(file.size)),
-                        style,
-                    ),
-                ]);
-
-                lines.push(line);
-            }
-
-            let paragraph = Paragraph::new(lines)
-                .block(Block::default().borders(Borders::ALL).title(title))
-                .wrap(Wrap { trim: false });
-            f.render_widget(paragraph, chunks[0]);
+            FileListWidget::render_files(f, app, chunks[0], title);
+            let Some(group) = app.current_group() else {
+                return;
+            };
 
             // Render action hints
             if let Some(file) = group.files.get(app.selected_file) {
@@ -427,6 +316,44 @@ impl MainLayout {
         }
     }
 
+    fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
+        let Some(status) = &app.status else {
+            return;
+        };
+        let color = match status.kind {
+            StatusKind::Info => Color::Cyan,
+            StatusKind::Success => Color::Green,
+            StatusKind::Warning => Color::Yellow,
+            StatusKind::Error => Color::Red,
+        };
+
+        let paragraph = Paragraph::new(Line::from(Span::styled(
+            format!(" {}", status.text),
+            Style::default().fg(color).bold(),
+        )));
+        f.render_widget(paragraph, area);
+    }
+
+    fn render_compare_help(f: &mut Frame, area: Rect) {
+        let help_text = vec![
+            Line::from(vec![
+                Span::styled("🔍 并排比较: ", Style::default().fg(Color::Cyan)),
+                Span::styled("Tab换右侧文件", Style::default().fg(Color::Green).bold()),
+                Span::styled(" ", Style::default()),
+                Span::styled("1保留左侧", Style::default().fg(Color::Green).bold()),
+                Span::styled(" ", Style::default()),
+                Span::styled("2保留右侧", Style::default().fg(Color::Green).bold()),
+                Span::styled(" | ", Style::default()),
+                Span::styled("Esc返回", Style::default().fg(Color::Yellow).bold()),
+            ]),
+        ];
+
+        let paragraph = Paragraph::new(help_text)
+            .alignment(Alignment::Center)
+            .style(Style::default().bg(Color::DarkGray).fg(Color::White).bold());
+        f.render_widget(paragraph, area);
+    }
+
     fn render_help_text(f: &mut Frame, area: Rect) {
         let help_text = vec![
             Line::from(vec![
@@ -2,8 +2,12 @@ pub mod main_layout;
 pub mod file_list;
 pub mod preview;
 pub mod help;
+pub mod confirm;
+pub mod compare;
 
 pub use main_layout::MainLayout;
 pub use file_list::FileListWidget;
 pub use preview::PreviewWidget;
 pub use help::HelpWidget;
+pub use confirm::ConfirmWidget;
+pub use compare::CompareWidget;
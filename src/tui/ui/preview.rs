@@ -1,13 +1,107 @@
-// This file is intentionally left minimal as the preview rendering
-// is handled in main_layout.rs for better layout coordination
+// Preview pane for the selected file
+//
+// Half-block previews are ordinary styled text. Protocol images are written
+// to the terminal by the event loop after the frame; here the cells they cover
+// are only marked as skipped so ratatui does not draw over the picture.
 
-use ratatui::Frame;
-use crate::tui::App;
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Color, Style},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::tui::graphics::{PreviewImage, PreviewSlot};
+use crate::tui::{App, Mode};
 
 pub struct PreviewWidget;
 
 impl PreviewWidget {
-    pub fn render(_f: &mut Frame, _app: &App) {
-        // Rendering handled in main_layout.rs
+    /// Preview of the selected file
+    pub fn render(f: &mut Frame, app: &mut App, area: Rect) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(" 🖼️  预览 ")
+            .title_style(Style::default().fg(Color::Cyan).bold());
+        Self::render_file(f, app, area, block, PreviewSlot::Primary, app.selected_file);
+    }
+
+    /// Preview of the file at `index` of the current group in the pane of
+    /// `slot`, inside `block`
+    pub fn render_file(f: &mut Frame, app: &mut App, area: Rect, mut block: Block, slot: PreviewSlot, index: usize) {
+        if let Some(protocol) = app.preview_protocol {
+            block = block.title_bottom(format!(" {} ", protocol.label()));
+        }
+        let inner = block.inner(area);
+
+        let file = app.current_group().and_then(|g| g.files.get(index));
+        let Some((id, is_image)) = file.map(|f| (f.id, f.is_image())) else {
+            f.render_widget(block, area);
+            return;
+        };
+        if !is_image {
+            Self::render_message(f, block, area, "🎬 视频暂不支持预览", Color::DarkGray);
+            return;
+        }
+        app.preview_wanted[slot.index()] = Some((id, inner));
+
+        let Some(preview) = app.preview_for(slot, id, inner) else {
+            Self::render_message(f, block, area, "⏳ 正在加载预览…", Color::DarkGray);
+            return;
+        };
+        if let Some((width, height)) = preview.dimensions {
+            block = block.title(format!(" {}×{} ", width, height));
+        }
+
+        match &preview.image {
+            PreviewImage::Unavailable(reason) => {
+                let text = format!("❌ 无法预览: {}", reason);
+                Self::render_message(f, block, area, &text, Color::Red);
+            }
+            PreviewImage::Blocks(lines) => {
+                let paragraph = Paragraph::new(lines.clone())
+                    .alignment(Alignment::Center)
+                    .block(block);
+                f.render_widget(paragraph, area);
+            }
+            // 图片会盖住弹出的对话框，先隐藏
+            PreviewImage::Graphic { .. } if app.mode == Mode::Confirm => {
+                Self::render_message(f, block, area, "（对话框打开时隐藏预览）", Color::DarkGray);
+            }
+            PreviewImage::Graphic { cols, rows, .. } => {
+                f.render_widget(block, area);
+                let placed = Self::reserve(f, inner, *cols, *rows);
+                app.graphic_placed[slot.index()] = Some((id, placed));
+            }
+        }
+    }
+
+    fn render_message(f: &mut Frame, block: Block, area: Rect, text: &str, color: Color) {
+        let paragraph = Paragraph::new(format!("\n{}", text))
+            .style(Style::default().fg(color))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .block(block);
+        f.render_widget(paragraph, area);
+    }
+
+    /// Skip the cells of a `cols` × `rows` area centered horizontally in
+    /// `inner`, and return it
+    fn reserve(f: &mut Frame, inner: Rect, cols: u16, rows: u16) -> Rect {
+        let width = cols.min(inner.width);
+        let area = Rect {
+            x: inner.x + (inner.width - width) / 2,
+            y: inner.y,
+            width,
+            height: rows.min(inner.height),
+        };
+
+        let buf = f.buffer_mut();
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                buf[(x, y)].set_skip(true);
+            }
+        }
+        area
     }
 }
@@ -1,4 +1,6 @@
 /// Utility functions for the DejaVu application
 pub mod format;
+pub mod path;
 
 pub use format::format_size;
+pub use path::numbered_name;
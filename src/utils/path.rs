@@ -0,0 +1,27 @@
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+
+/// Candidate file names for avoiding collisions
+///
+/// Returns `name` for `n == 1`, then `stem.2.ext`, `stem.3.ext`, ...
+///
+/// # Examples
+/// ```ignore
+/// use std::ffi::OsStr;
+///
+/// assert_eq!(numbered_name(OsStr::new("a.jpg"), 2), "a.2.jpg");
+/// ```
+pub fn numbered_name(name: &OsStr, n: usize) -> OsString {
+    if n == 1 {
+        return name.to_os_string();
+    }
+
+    let path = Path::new(name);
+    let mut numbered = path.file_stem().unwrap_or(name).to_os_string();
+    numbered.push(format!(".{}", n));
+    if let Some(extension) = path.extension() {
+        numbered.push(".");
+        numbered.push(extension);
+    }
+    numbered
+}